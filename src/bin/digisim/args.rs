@@ -0,0 +1,92 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use crate::CliError;
+
+/// Command line arguments, split into positional arguments, `--name value` options and `--name`
+/// flags.
+#[derive(Debug, Default)]
+pub struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: HashSet<String>,
+}
+
+impl Args {
+    /// Parses the arguments, where the options named in `with_value` take the following argument
+    /// as their value and the ones named in `flags` take none.
+    pub fn parse(args: impl IntoIterator<Item = String>, with_value: &[&str], flags: &[&str]) -> Result<Self, CliError> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg);
+                continue;
+            };
+
+            if with_value.contains(&name) {
+                let value = args.next().ok_or_else(|| CliError::Usage(format!("Option '--{}' requires a value.", name)))?;
+                parsed.options.insert(name.into(), value);
+            } else if flags.contains(&name) {
+                parsed.flags.insert(name.into());
+            } else {
+                return Err(CliError::Usage(format!("Unknown option '--{}'.", name)));
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|x| x.as_str())
+    }
+
+    /// Parses the value of an option, e.g. a number of ticks.
+    pub fn parse_option<T: FromStr>(&self, name: &str) -> Result<Option<T>, CliError> {
+        self.option(name)
+            .map(|x| x.parse().map_err(|_| CliError::Usage(format!("Invalid value '{}' for option '--{}'.", x, name))))
+            .transpose()
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    /// Returns the positional argument, naming it in the error if it is missing.
+    pub fn positional(&self, index: usize, name: &str) -> Result<&str, CliError> {
+        self.positional.get(index).map(|x| x.as_str()).ok_or_else(|| CliError::Usage(format!("Missing argument {}.", name)))
+    }
+
+    /// Fails if there are more than `count` positional arguments.
+    pub fn expect_positional(&self, count: usize) -> Result<(), CliError> {
+        match self.positional.get(count) {
+            Some(extra) => Err(CliError::Usage(format!("Unexpected argument '{}'.", extra))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, CliError> {
+        Args::parse(args.iter().map(|x| x.to_string()), &["ticks", "project"], &["flat"])
+    }
+
+    #[test]
+    fn options_and_positional() {
+        let args = parse(&["circuit.json", "--ticks", "20", "--flat", "out.dot"]).unwrap();
+        assert_eq!(args.positional(0, "CIRCUIT").unwrap(), "circuit.json");
+        assert_eq!(args.positional(1, "OUTPUT").unwrap(), "out.dot");
+        assert_eq!(args.parse_option::<usize>("ticks").unwrap(), Some(20));
+        assert_eq!(args.option("project"), None);
+        assert!(args.flag("flat"));
+        assert!(args.expect_positional(2).is_ok());
+        assert!(args.expect_positional(1).is_err());
+
+        assert!(matches!(parse(&["--ticks"]), Err(CliError::Usage(_))));
+        assert!(matches!(parse(&["--unknown"]), Err(CliError::Usage(_))));
+        assert!(parse(&["--ticks", "many"]).unwrap().parse_option::<usize>("ticks").is_err());
+    }
+}
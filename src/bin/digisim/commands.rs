@@ -0,0 +1,312 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+use serde::de::DeserializeOwned;
+
+use digisim::{Circuit, Simulation};
+use digisim::circuit::{CircuitDefinition, Registry};
+use digisim::circuit::registry::REGISTRY;
+use digisim::component::ComponentDefinition;
+use digisim::component::definition::TruthTable;
+use digisim::export;
+use digisim::project::Project;
+use digisim::sim::Settings;
+use digisim::validation::{self, ValidationReport};
+use crate::args::Args;
+use crate::CliError;
+
+const DEFAULT_TICKS: usize = 100;
+
+/// `digisim run CIRCUIT`
+pub fn run(args: impl Iterator<Item = String>) -> Result<ExitCode, CliError> {
+    let args = Args::parse(args, &["project", "registry", "ticks", "max-delay"], &[])?;
+    args.expect_positional(1)?;
+    let (registry, project) = load_registry(&args)?;
+    let circuit_def = load_circuit(args.positional(0, "CIRCUIT")?, project.as_ref())?;
+
+    let mut settings = Settings::default();
+    if let Some(max_delay) = args.parse_option("max-delay")? {
+        settings.max_delay = max_delay;
+    }
+
+    let mut sim = Simulation::with_circuit(settings, Circuit::from_definition(&registry, circuit_def)?);
+    sim.init();
+    sim.tick_for(args.parse_option("ticks")?.unwrap_or(DEFAULT_TICKS));
+
+    let state: BTreeMap<_, _> = sim.state().data.into_iter().collect();
+    println!("{}", serde_json::to_string_pretty(&state).unwrap());
+    Ok(ExitCode::SUCCESS)
+}
+
+/// `digisim validate DEFINITION REQUIREMENTS`
+pub fn validate(args: impl Iterator<Item = String>) -> Result<ExitCode, CliError> {
+    let args = Args::parse(args, &["project", "registry", "kind"], &[])?;
+    args.expect_positional(2)?;
+    let (registry, _) = load_registry(&args)?;
+    let def = load_definition(args.positional(0, "DEFINITION")?, &registry)?;
+    let requirements = Path::new(args.positional(1, "REQUIREMENTS")?);
+
+    // Validation resolves the definitions in the global registry
+    REGISTRY.with(|reg| reg.lock().replace(registry));
+    let report: ValidationReport = match args.option("kind").unwrap_or("combinational") {
+        "combinational" => validation::test_combinational(def, read_json(requirements)?),
+        "sequential" => validation::test_sequential(def, read_json(requirements)?),
+        "fsm" => validation::test_fsm(def, read_json(requirements)?),
+        "equivalence" => validation::test_equivalence(def, read_json(requirements)?),
+        "random" => validation::test_random(def, read_json(requirements)?),
+        "reference" => validation::test_reference(def, read_json(requirements)?),
+        kind => return Err(CliError::Usage(format!("Unknown requirements kind '{}'.", kind))),
+    };
+
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    Ok(if report.success() { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+/// `digisim export FORMAT SOURCE`
+pub fn export(args: impl Iterator<Item = String>) -> Result<ExitCode, CliError> {
+    let args = Args::parse(args, &["project", "registry", "ticks", "output"], &["flat", "definition"])?;
+    args.expect_positional(2)?;
+    let (mut registry, project) = load_registry(&args)?;
+    let source = args.positional(1, "SOURCE")?;
+
+    let out = match args.positional(0, "FORMAT")? {
+        "verilog" if args.flag("definition") => {
+            let def = load_definition(source, &registry)?;
+            let id = def.id;
+            registry.insert(def);
+            export::definition_to_verilog(&registry, id)?
+        },
+        format @ ("dot" | "verilog" | "vcd") => {
+            let circuit_def = load_circuit(source, project.as_ref())?;
+            let name = circuit_def.name.clone();
+            if format == "dot" && !args.flag("flat") {
+                export::circuit_definition_to_dot(&registry, &circuit_def)?
+            } else {
+                let circuit = Circuit::from_definition(&registry, circuit_def)?;
+                match format {
+                    "dot" => export::circuit_to_dot(&circuit, &name),
+                    "verilog" => export::circuit_to_verilog(&circuit, &name)?,
+                    _ => {
+                        let mut sim = Simulation::with_circuit(Settings::default(), circuit);
+                        sim.init();
+                        export::simulation_to_vcd(&mut sim, args.parse_option("ticks")?.unwrap_or(DEFAULT_TICKS))
+                    },
+                }
+            }
+        },
+        format => return Err(CliError::Usage(format!("Unknown export format '{}'.", format))),
+    };
+
+    match args.option("output") {
+        Some(path) => fs::write(path, out).map_err(|e| CliError::Io(path.into(), e))?,
+        None => print!("{}", out),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// `digisim table DEFINITION`
+pub fn table(args: impl Iterator<Item = String>) -> Result<ExitCode, CliError> {
+    let args = Args::parse(args, &["project", "registry"], &["json"])?;
+    args.expect_positional(1)?;
+    let (registry, _) = load_registry(&args)?;
+    let def = load_definition(args.positional(0, "DEFINITION")?, &registry)?;
+    let table = TruthTable::from_definition(&registry, &def)?;
+
+    if args.flag("json") {
+        println!("{}", serde_json::to_string_pretty(&table).unwrap());
+    } else {
+        print!("{}", format_table(&def, &table));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Formats the table with a column per pin, marking the rows which did not settle.
+fn format_table(def: &ComponentDefinition, table: &TruthTable) -> String {
+    let width = |name: &String| name.len().max(1);
+    let cells = |names: &[String], values: &[bool]| -> Vec<String> {
+        names.iter().zip(values.iter()).map(|(name, &value)| format!("{:<1$}", value as u8, width(name))).collect()
+    };
+
+    let mut out = format!("{} | {}\n", def.pins.input.join(" "), def.pins.output.join(" "));
+    for (row, (inputs, outputs)) in table.iter().enumerate() {
+        let line = format!("{} | {}", cells(&def.pins.input, inputs).join(" "), cells(&def.pins.output, outputs).join(" "));
+        if table.unstable.contains(&row) {
+            out.push_str(&format!("{}  (unstable)\n", line.trim_end()));
+        } else {
+            out.push_str(&format!("{}\n", line.trim_end()));
+        }
+    }
+    out
+}
+
+/// Builds the registry of the project given by `--project`, or of only the prebuilt definitions,
+/// and adds the definitions of the registry file given by `--registry`.
+pub fn load_registry(args: &Args) -> Result<(Registry, Option<Project>), CliError> {
+    let (mut registry, project) = match args.option("project") {
+        Some(path) => {
+            let json = fs::read_to_string(path).map_err(|e| CliError::Io(path.into(), e))?;
+            let project = Project::from_json(&json)?;
+            (project.registry()?, Some(project))
+        },
+        None => (Registry::default(), None),
+    };
+
+    // Registry files written by `get_registry` contain the prebuilt definitions too
+    if let Some(path) = args.option("registry") {
+        let file: Registry = read_json(Path::new(path))?;
+        let mut definitions: Vec<ComponentDefinition> = file.definitions().filter(|x| x.id >= 0).cloned().collect();
+        definitions.sort_by_key(|x| x.id);
+        for def in definitions {
+            registry.try_insert(def)?;
+        }
+    }
+
+    Ok((registry, project))
+}
+
+/// Loads a circuit from a file, or from the project if given as `#ID`.
+pub fn load_circuit(source: &str, project: Option<&Project>) -> Result<CircuitDefinition, CliError> {
+    match parse_id(source)? {
+        Some(id) => project.and_then(|x| x.circuits.iter().find(|x| x.id == id))
+            .cloned()
+            .ok_or(CliError::NotFound("circuit", id)),
+        None => read_json(Path::new(source)),
+    }
+}
+
+/// Loads a definition from a file, or from the registry if given as `#ID`.
+pub fn load_definition(source: &str, registry: &Registry) -> Result<ComponentDefinition, CliError> {
+    match parse_id(source)? {
+        Some(id) => registry.get_definition(id).cloned().map_err(|_| CliError::NotFound("definition", id)),
+        None => read_json(Path::new(source)),
+    }
+}
+
+fn parse_id(source: &str) -> Result<Option<i32>, CliError> {
+    source.strip_prefix('#')
+        .map(|x| x.parse().map_err(|_| CliError::Usage(format!("Invalid ID '{}'.", source))))
+        .transpose()
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, CliError> {
+    let json = fs::read_to_string(path).map_err(|e| CliError::Io(path.into(), e))?;
+    serde_json::from_str(&json).map_err(|e| CliError::Format(path.into(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn asset(name: &str) -> String {
+        format!("{}/tests/assets/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter().map(|x| x.to_string()).collect::<Vec<_>>().into_iter()
+    }
+
+    /// Writes a project with the AND and NOT gates and the NAND circuit built from them.
+    fn nand_project(name: &str) -> PathBuf {
+        let read = |name: &str| serde_json::from_str::<serde_json::Value>(&fs::read_to_string(asset(name)).unwrap()).unwrap();
+        let project = serde_json::json!({
+            "version": digisim::project::CURRENT_VERSION,
+            "metadata": { "name": "Nand" },
+            "definitions": [read("and_gate_definition.json"), read("not_gate_definition.json")],
+            "circuits": [read("nand_gate_circuit.json")],
+        });
+
+        let path = std::env::temp_dir().join(format!("digisim-{}-{}.json", name, std::process::id()));
+        fs::write(&path, project.to_string()).unwrap();
+        path
+    }
+
+    /// Writes a registry with the fixture definitions in the format of `get_registry`.
+    fn fixture_registry(name: &str) -> PathBuf {
+        let mut registry = Registry::default();
+        for def in ["and_gate_definition.json", "not_gate_definition.json", "ab_inverted_definition.json"] {
+            registry.insert(read_json(Path::new(&asset(def))).unwrap());
+        }
+
+        let path = std::env::temp_dir().join(format!("digisim-{}-{}.json", name, std::process::id()));
+        fs::write(&path, serde_json::to_string(&registry).unwrap()).unwrap();
+        path
+    }
+
+    #[test]
+    fn run_with_registry() {
+        let circuit = asset("ab_inverted_on_not_circuit.json");
+        assert!(matches!(run(args(&[&circuit])), Err(CliError::DefinitionError(_))));
+
+        let path = fixture_registry("registry");
+        let registry = path.to_str().unwrap();
+        assert_eq!(run(args(&[&circuit, "--registry", registry])).unwrap(), ExitCode::SUCCESS);
+        assert_eq!(export(args(&["verilog", &asset("nand_gate_circuit.json"), "--registry", registry])).unwrap(), ExitCode::SUCCESS);
+
+        // The registry adds to the definitions of the project
+        let project = nand_project("registry-project");
+        assert_eq!(run(args(&["--project", project.to_str().unwrap(), "--registry", registry, &circuit])).unwrap(), ExitCode::SUCCESS);
+        fs::remove_file(project).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn run_circuit() {
+        let path = nand_project("run");
+        let project = path.to_str().unwrap();
+        assert_eq!(run(args(&["--project", project, "#0", "--ticks", "20"])).unwrap(), ExitCode::SUCCESS);
+        assert!(matches!(run(args(&["--project", project, "#5"])), Err(CliError::NotFound("circuit", 5))));
+        assert!(matches!(run(args(&[&asset("missing.json")])), Err(CliError::Io(..))));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn validate_definition() {
+        let requirements = asset("and_gate_requirements.json");
+        assert_eq!(validate(args(&[&asset("and_gate_definition.json"), &requirements])).unwrap(), ExitCode::SUCCESS);
+        assert_eq!(validate(args(&[&asset("not_gate_definition.json"), &requirements])).unwrap(), ExitCode::FAILURE);
+
+        // Builtins cannot be simulated on their own, which fails the report instead of panicking
+        assert_eq!(validate(args(&["#-1", &requirements])).unwrap(), ExitCode::FAILURE);
+        assert!(matches!(validate(args(&["#-1", &requirements, "--kind", "other"])), Err(CliError::Usage(_))));
+    }
+
+    #[test]
+    fn export_formats() {
+        let path = nand_project("export");
+        let project = path.to_str().unwrap();
+        for format in ["dot", "verilog", "vcd"] {
+            assert_eq!(export(args(&[format, "--project", project, "#0"])).unwrap(), ExitCode::SUCCESS, "{}", format);
+        }
+        assert_eq!(export(args(&["dot", "--flat", "--project", project, "#0"])).unwrap(), ExitCode::SUCCESS);
+        assert_eq!(export(args(&["verilog", "--definition", &asset("and_gate_definition.json")])).unwrap(), ExitCode::SUCCESS);
+
+        let output = std::env::temp_dir().join(format!("digisim-export-{}.vcd", std::process::id()));
+        export(args(&["vcd", "--project", project, "#0", "--ticks", "10", "--output", output.to_str().unwrap()])).unwrap();
+        assert!(fs::read_to_string(&output).unwrap().contains("$enddefinitions"));
+        assert!(matches!(export(args(&["svg", "--project", project, "#0"])), Err(CliError::Usage(_))));
+        fs::remove_file(output).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn truth_table() {
+        let def = asset("and_gate_definition.json");
+        assert_eq!(table(args(&[&def])).unwrap(), ExitCode::SUCCESS);
+        assert_eq!(table(args(&[&def, "--json"])).unwrap(), ExitCode::SUCCESS);
+        assert!(matches!(table(args(&["#7"])), Err(CliError::NotFound("definition", 7))));
+    }
+
+    #[test]
+    fn formatted_table() {
+        let def: ComponentDefinition = read_json(Path::new(&asset("and_gate_definition.json"))).unwrap();
+        let mut table = TruthTable::from_definition(&Registry::default(), &def).unwrap();
+        assert_eq!(format_table(&def, &table), "A B | Y\n0 0 | 0\n0 1 | 0\n1 0 | 0\n1 1 | 1\n");
+
+        table.unstable = vec![2];
+        assert_eq!(format_table(&def, &table), "A B | Y\n0 0 | 0\n0 1 | 0\n1 0 | 0  (unstable)\n1 1 | 1\n");
+    }
+}
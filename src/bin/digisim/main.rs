@@ -0,0 +1,123 @@
+//! Command line interface for simulating, validating and exporting circuits without a browser.
+
+mod args;
+mod commands;
+mod repl;
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use digisim::circuit::{DefinitionError, InspectionError};
+use digisim::export::ExportError;
+use digisim::project::ProjectError;
+use digisim::sim::UserEventError;
+use digisim::validation::ConversionError;
+
+const USAGE: &str = "\
+Usage: digisim <COMMAND> [OPTIONS]
+
+Commands:
+    run CIRCUIT                     Simulates the circuit and prints the state of its outputs
+        --ticks N                   Number of ticks to simulate [default: 100]
+        --max-delay N               Largest component delay [default: 2048]
+    validate DEFINITION REQUIREMENTS
+                                    Tests the definition, exiting with 1 if it fails
+        --kind KIND                 combinational, sequential, fsm, equivalence, random or
+                                    reference [default: combinational]
+    export FORMAT SOURCE            Exports a circuit as dot, verilog or vcd
+        --flat                      Draws the flattened circuit in dot
+        --definition                Exports SOURCE as a definition in verilog
+        --ticks N                   Number of ticks to record in vcd [default: 100]
+        --output FILE               Writes to the file instead of the standard output
+    table DEFINITION                Prints the truth table of a transparent definition
+        --json                      Prints the table as JSON
+    repl [CIRCUIT]                  Starts an interactive shell for stepping through a simulation
+
+Common options:
+    --project FILE                  Loads the definitions and circuits of a project
+    --registry FILE                 Loads the definitions of a registry, as saved by get_registry
+
+Circuits and definitions are read from JSON files, or from the project by their ID as '#ID'.
+
+Exit codes:
+    0                               Success, or the definition passed validation
+    1                               The definition failed validation
+    2                               Invalid usage, or the input could not be loaded or processed";
+
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    #[error("{0}")]
+    Usage(String),
+
+    #[error("Cannot read '{0}'.")]
+    Io(PathBuf, #[source] std::io::Error),
+
+    #[error("Cannot parse '{0}'.")]
+    Format(PathBuf, #[source] serde_json::Error),
+
+    #[error("No {0} with ID {1} was found.")]
+    NotFound(&'static str, i32),
+
+    #[error("Failed to load the project.")]
+    ProjectError(#[from] ProjectError),
+
+    #[error("Failed to build the circuit.")]
+    DefinitionError(#[from] DefinitionError),
+
+    #[error("Failed to export.")]
+    ExportError(#[from] ExportError),
+
+    #[error("Failed to simulate the definition.")]
+    ConversionError(#[from] ConversionError),
+
+    #[error("Failed to inspect the circuit.")]
+    InspectionError(#[from] InspectionError),
+
+    #[error("Failed to process the user event.")]
+    UserEventError(#[from] UserEventError),
+}
+
+/// Describes the error together with the errors it wraps, which only describe themselves.
+pub fn describe(e: &CliError) -> String {
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(" {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(command) = args.next() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let result = match command.as_str() {
+        "run" => commands::run(args),
+        "validate" => commands::validate(args),
+        "export" => commands::export(args),
+        "table" => commands::table(args),
+        "repl" => repl::repl(args),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(ExitCode::SUCCESS)
+        },
+        _ => Err(CliError::Usage(format!("Unknown command '{}'.", command))),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(CliError::Usage(message)) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        },
+        Err(e) => {
+            eprintln!("error: {}", describe(&e));
+            ExitCode::from(2)
+        },
+    }
+}
@@ -0,0 +1,221 @@
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+use digisim::{Circuit, Simulation};
+use digisim::circuit::{ComponentPath, Id, Registry};
+use digisim::component::{Button, Led, Switch};
+use digisim::project::Project;
+use digisim::sim::{Settings, UserEvent};
+use crate::args::Args;
+use crate::commands::{load_circuit, load_registry};
+use crate::{describe, CliError};
+
+const HELP: &str = "\
+Commands:
+    load CIRCUIT        Loads a circuit from a file, or from the project as '#ID'
+    toggle PATH         Toggles the switch at the component path, e.g. '1'
+    press PATH          Presses the button at the component path
+    release PATH        Releases the button at the component path
+    pulse PATH          Presses the button and releases it after its pulse width
+    tick [N]            Advances the simulation by N ticks [default: 1]
+    show                Prints the LEDs and watched connectors
+    watch PATH:PIN      Prints the output pin after every command, e.g. '0/1:2'
+    unwatch PATH:PIN    Stops printing the output pin
+    reset               Resets the circuit to its initial state
+    help                Prints this message
+    quit                Exits the shell";
+
+/// `digisim repl [CIRCUIT]`
+pub fn repl(args: impl Iterator<Item = String>) -> Result<ExitCode, CliError> {
+    let args = Args::parse(args, &["project", "registry"], &[])?;
+    args.expect_positional(1)?;
+    let (registry, project) = load_registry(&args)?;
+    let mut repl = Repl { registry, project, sim: None, watched: Vec::new() };
+    if let Ok(circuit) = args.positional(0, "CIRCUIT") {
+        println!("{}", repl.execute(&format!("load {}", circuit))?);
+    }
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        io::stdout().flush().unwrap();
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+
+        match line.trim() {
+            "" => continue,
+            "quit" | "exit" => break,
+            line => match repl.execute(line) {
+                Ok(out) => println!("{}", out),
+                Err(e) => println!("error: {}", describe(&e)),
+            },
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// An interactive session over a simulation, executing one command at a time.
+struct Repl {
+    registry: Registry,
+    project: Option<Project>,
+    sim: Option<Simulation>,
+    /// Output pins printed after every command.
+    watched: Vec<(ComponentPath, u32)>,
+}
+
+impl Repl {
+    /// Executes the command, returning what to print.
+    fn execute(&mut self, line: &str) -> Result<String, CliError> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let argument = words.next();
+        if let Some(extra) = words.next() {
+            return Err(CliError::Usage(format!("Unexpected argument '{}'.", extra)));
+        }
+        let required = |name: &str| argument.ok_or_else(|| CliError::Usage(format!("Missing argument {}.", name)));
+
+        match command {
+            "load" => {
+                let circuit_def = load_circuit(required("CIRCUIT")?, self.project.as_ref())?;
+                let mut sim = Simulation::with_circuit(Settings::default(), Circuit::from_definition(&self.registry, circuit_def)?);
+                sim.enable_inspection();
+                sim.init();
+                self.sim = Some(sim);
+                self.watched.clear();
+            },
+            "toggle" | "press" | "release" | "pulse" => {
+                let path = parse_path(required("PATH")?)?;
+                let sim = self.sim()?;
+                let id = sim.circuit().hierarchy.id_of(&path).ok_or_else(|| CliError::Usage(format!("No component at '{}'.", path)))?;
+                let component = sim.circuit().components[&id].as_any();
+                if command == "toggle" && !component.is::<Switch>() {
+                    return Err(CliError::Usage(format!("Component at '{}' is not a switch.", path)));
+                }
+                if command != "toggle" && !component.is::<Button>() {
+                    return Err(CliError::Usage(format!("Component at '{}' is not a button.", path)));
+                }
+                sim.process_user_event(UserEvent { component_id: id, payload: command.into() })?;
+            },
+            "tick" => {
+                let ticks = argument.map(|x| x.parse().map_err(|_| CliError::Usage(format!("Invalid number of ticks '{}'.", x))))
+                    .transpose()?
+                    .unwrap_or(1);
+                self.sim()?.tick_for(ticks);
+            },
+            "show" => {},
+            "watch" => {
+                let (path, pin) = parse_connector(required("PATH:PIN")?)?;
+                self.sim()?.value_at(&path, pin)?;
+                if !self.watched.contains(&(path.clone(), pin)) {
+                    self.watched.push((path, pin));
+                }
+            },
+            "unwatch" => {
+                let connector = parse_connector(required("PATH:PIN")?)?;
+                self.watched.retain(|x| *x != connector);
+            },
+            "reset" => {
+                let sim = self.sim()?;
+                sim.reset();
+                sim.init();
+            },
+            "help" => return Ok(HELP.into()),
+            _ => return Err(CliError::Usage(format!("Unknown command '{}', see 'help'.", command))),
+        }
+
+        self.status()
+    }
+
+    fn sim(&mut self) -> Result<&mut Simulation, CliError> {
+        self.sim.as_mut().ok_or_else(|| CliError::Usage("No circuit is loaded, see 'load'.".into()))
+    }
+
+    /// Prints the elapsed ticks, the LEDs and the watched output pins, which are `x` until driven.
+    fn status(&mut self) -> Result<String, CliError> {
+        let watched = self.watched.clone();
+        let sim = self.sim()?;
+        let circuit = sim.circuit();
+        let path = |id: Id| circuit.hierarchy.path_of(id).map_or_else(|| id.to_string(), |x| x.to_string());
+
+        let mut leds: Vec<(String, bool)> = circuit.components.iter()
+            .filter_map(|(&id, x)| x.as_any().downcast_ref::<Led>().map(|x| (path(id), x.value())))
+            .collect();
+        leds.sort();
+
+        let mut out = format!("tick {}", sim.elapsed());
+        for (path, value) in leds {
+            write!(out, "\nLED {} = {}", path, value as u8).unwrap();
+        }
+        for (path, pin) in watched {
+            let value = sim.value_at(&path, pin)?.map_or("x".into(), |x| (x as u8).to_string());
+            write!(out, "\n{}:{} = {}", path, pin, value).unwrap();
+        }
+        Ok(out)
+    }
+}
+
+fn parse_path(path: &str) -> Result<ComponentPath, CliError> {
+    path.parse().map_err(|_| CliError::Usage(format!("Invalid component path '{}'.", path)))
+}
+
+/// Parses a connector as the component path and pin, e.g. `0/1:2`.
+fn parse_connector(connector: &str) -> Result<(ComponentPath, u32), CliError> {
+    let invalid = || CliError::Usage(format!("Invalid connector '{}', expected PATH:PIN.", connector));
+    let (path, pin) = connector.rsplit_once(':').ok_or_else(invalid)?;
+    Ok((parse_path(path)?, pin.parse().map_err(|_| invalid())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use digisim::component::ComponentDefinition;
+
+    #[test]
+    fn stepping_session() {
+        let mut registry = Registry::default();
+        let and: ComponentDefinition = serde_json::from_str(include_str!("../../../tests/assets/and_gate_definition.json")).unwrap();
+        registry.insert(and);
+
+        // Switches 1 and 2 drive the AND gate 3, lighting up the LED 4
+        let circuit = serde_json::json!({
+            "id": 0,
+            "name": "And",
+            "description": "",
+            "components": [
+                { "definitionId": -6, "id": 1 },
+                { "definitionId": -6, "id": 2 },
+                { "definitionId": 1, "id": 3 },
+                { "definitionId": -7, "id": 4 },
+            ],
+            "connections": [
+                { "from": { "componentId": 1, "pin": 0 }, "to": [{ "componentId": 3, "pin": 0 }] },
+                { "from": { "componentId": 2, "pin": 0 }, "to": [{ "componentId": 3, "pin": 1 }] },
+                { "from": { "componentId": 3, "pin": 2 }, "to": [{ "componentId": 4, "pin": 0 }] },
+            ],
+        });
+        let project: Project = serde_json::from_value(serde_json::json!({
+            "version": digisim::project::CURRENT_VERSION,
+            "metadata": { "name": "Session" },
+            "definitions": [],
+            "circuits": [circuit],
+        })).unwrap();
+
+        let mut repl = Repl { registry, project: Some(project), sim: None, watched: Vec::new() };
+        assert!(matches!(repl.execute("tick"), Err(CliError::Usage(_))));
+        assert_eq!(repl.execute("load #0").unwrap(), "tick 0\nLED 4 = 0");
+        assert_eq!(repl.execute("watch 3/0:2").unwrap(), "tick 0\nLED 4 = 0\n3/0:2 = x");
+
+        repl.execute("toggle 1").unwrap();
+        repl.execute("toggle 2").unwrap();
+        assert_eq!(repl.execute("tick 20").unwrap(), "tick 20\nLED 4 = 1\n3/0:2 = 0");
+        assert!(matches!(repl.execute("toggle 3"), Err(CliError::Usage(_))));
+        assert!(matches!(repl.execute("press 1"), Err(CliError::Usage(_))));
+
+        assert_eq!(repl.execute("reset").unwrap(), "tick 20\nLED 4 = 0\n3/0:2 = x");
+        assert_eq!(repl.execute("unwatch 3/0:2").unwrap(), "tick 20\nLED 4 = 0");
+    }
+}
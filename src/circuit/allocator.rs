@@ -0,0 +1,44 @@
+use std::collections::HashSet;
+use super::Id;
+
+/// Hands out component IDs which are not taken by any other component in the circuit.
+///
+/// `Id::MAX` is always reserved for the circuit's `Wiring` component.
+#[derive(Debug)]
+pub struct IdAllocator {
+    taken: HashSet<Id>,
+    next: Id,
+}
+
+impl IdAllocator {
+    /// Marks the ID as taken.
+    ///
+    /// # Returns
+    /// False if the ID was already taken.
+    pub fn reserve(&mut self, id: Id) -> bool {
+        self.taken.insert(id)
+    }
+
+    /// Allocates the lowest free ID.
+    ///
+    /// # Returns
+    /// None if the whole ID space is taken.
+    pub fn allocate(&mut self) -> Option<Id> {
+        while self.taken.contains(&self.next) {
+            self.next = self.next.checked_add(1)?;
+        }
+
+        self.taken.insert(self.next);
+        Some(self.next)
+    }
+}
+
+impl Default for IdAllocator {
+    fn default() -> Self {
+        Self {
+            taken: HashSet::from([Id::MAX]),
+            next: 0,
+        }
+    }
+}
+
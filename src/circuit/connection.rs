@@ -1,6 +1,6 @@
 use super::Connector;
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub struct Connection {
     pub from: Connector,
     pub to: Vec<Connector>,
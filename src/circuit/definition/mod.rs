@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use crate::component::definition::Component;
 use super::{Connection, Params, Id};
 
-#[derive(Debug, Default, PartialEq, Clone, serde::Deserialize)]
+#[derive(Debug, Default, PartialEq, Clone, serde::Deserialize, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CircuitDefinition {
     pub id: i32,
@@ -12,6 +12,7 @@ pub struct CircuitDefinition {
     pub desc: String,
     pub components: Vec<Component>,
     pub connections: Vec<Connection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub params: Option<HashMap<Id, Params>>,
 }
 
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+use super::Id;
+
+/// Path of a component through the transparent components containing it.
+///
+/// Each segment is the component ID local to the definition it is declared in, e.g. `0/3/1` is
+/// the component `1` inside the component `3` inside the top-level component `0`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct ComponentPath(pub Vec<Id>);
+
+impl ComponentPath {
+    pub fn top_level(id: Id) -> Self {
+        Self(vec![id])
+    }
+
+    /// Returns the path of the component declared inside this component.
+    pub fn child(&self, id: Id) -> Self {
+        let mut path = self.0.clone();
+        path.push(id);
+        Self(path)
+    }
+
+    /// Returns the path of the component containing this component.
+    pub fn parent(&self) -> Option<Self> {
+        match self.0.split_last() {
+            Some((_, rest)) if !rest.is_empty() => Some(Self(rest.to_vec())),
+            _ => None,
+        }
+    }
+
+    /// Returns the component ID local to its definition.
+    pub fn local_id(&self) -> Option<Id> {
+        self.0.last().copied()
+    }
+
+    /// Returns the number of nested components, top-level components having a depth of 1.
+    pub fn depth(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl Display for ComponentPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let segments = self.0.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        write!(f, "{}", segments.join("/"))
+    }
+}
+
+impl FromStr for ComponentPath {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let segments = s.split('/').map(|x| x.trim().parse()).collect::<Result<Vec<Id>, _>>()?;
+        Ok(Self(segments))
+    }
+}
+
+/// Bidirectional mapping between flattened component IDs and their hierarchical paths.
+#[derive(Debug, Default, Clone)]
+pub struct Hierarchy {
+    paths: HashMap<Id, ComponentPath>,
+    ids: HashMap<ComponentPath, Id>,
+}
+
+impl Hierarchy {
+    pub fn insert(&mut self, id: Id, path: ComponentPath) {
+        self.ids.insert(path.clone(), id);
+        self.paths.insert(id, path);
+    }
+
+    pub fn path_of(&self, id: Id) -> Option<&ComponentPath> {
+        self.paths.get(&id)
+    }
+
+    pub fn id_of(&self, path: &ComponentPath) -> Option<Id> {
+        self.ids.get(path).copied()
+    }
+}
+
@@ -5,17 +5,21 @@ mod state;
 pub mod registry;
 mod definition;
 mod params;
+mod allocator;
+mod hierarchy;
 pub use id::Id;
 pub use connector::Connector;
 pub use connection::Connection;
-pub use state::CircuitState;
+pub use state::{CircuitState, InspectionError};
 pub use registry::Registry;
 pub use definition::CircuitDefinition;
 pub use params::Params;
+pub use allocator::IdAllocator;
+pub use hierarchy::{ComponentPath, Hierarchy};
 
 use std::collections::HashMap;
 use crate::component::definition::ComponentKind;
-use crate::component::{self, Component, ComponentDefinition, Generic, Wiring};
+use crate::component::{self, Component, ComponentDefinition, Wiring};
 use DefinitionError::*;
 use self::registry::PREBUILT_REGISTRY;
 use rassert_rs::rassert;
@@ -29,10 +33,23 @@ pub struct Circuit {
 
     pub rerouted_defs: HashMap<Id, ComponentDefinition>,
 
+    /// Maps flattened component IDs to the paths the user sees, and vice versa.
+    pub hierarchy: Hierarchy,
+
     /// Maps components IDs to their corresponding component definition ID
     ///
     /// Note: Used only during definition building.
     definition_mapping: HashMap<Id, i32>,
+
+    /// Definition IDs of the transparent components currently being expanded
+    ///
+    /// Note: Used only during definition building.
+    expansion_stack: Vec<i32>,
+
+    /// Allocates IDs for components flattened out of transparent components
+    ///
+    /// Note: Used only during definition building.
+    allocator: IdAllocator,
 }
 
 impl Circuit {
@@ -45,6 +62,12 @@ impl Circuit {
         let mut circuit = Circuit::default();
         let mut transparent_components = Vec::new();
 
+        // Reserve top-level IDs so that flattened components never take their place
+        for component in circuit_def.components.iter() {
+            rassert!(circuit.allocator.reserve(component.id), ComponentIdAlreadyTaken(component.id));
+            circuit.hierarchy.insert(component.id, ComponentPath::top_level(component.id));
+        }
+
         for &component in circuit_def.components.iter() {
             let component_def = registry.get_definition(component.def_id)?;
             let ctx = Context {
@@ -120,8 +143,9 @@ impl Circuit {
             }
         }
 
-        // Discard definition mapping
+        // Discard definition mapping and the allocator
         circuit.definition_mapping.clear();
+        circuit.allocator = IdAllocator::default();
 
         Ok(circuit)
     }
@@ -155,10 +179,23 @@ impl Circuit {
     fn process_transparent(&mut self, ctx: Context) -> Result<(), DefinitionError> {
         let mut transparent_components = Vec::new();
 
-        // Reroute the component definition
-        let rerouted_def = ctx.component_def.reroute_component_def(self.components.len() as u32);
-        let rerouted_circuit = rerouted_def.circuit.as_ref().ok_or(InvalidTransparentComponent("No circuit field".into()))?;
+        // Guard against definitions which (transitively) contain themselves
+        if let Some(pos) = self.expansion_stack.iter().position(|&id| id == ctx.component_def.id) {
+            let mut cycle = self.expansion_stack[pos..].to_vec();
+            cycle.push(ctx.component_def.id);
+            return Err(RecursiveDefinition(cycle));
+        }
+        self.expansion_stack.push(ctx.component_def.id);
+
+        // Allocate fresh IDs for the inner components and reroute the component definition
+        let path = self.hierarchy.path_of(ctx.component.id).cloned().unwrap_or_default();
+        let id_map = self.allocate_inner_ids(ctx.component_def, &path)?;
+        let rerouted_def = ctx.component_def.remap_component_def(|id| id_map[&id])?;
+        let rerouted_circuit = rerouted_def.circuit.as_ref().unwrap();
         self.rerouted_defs.insert(ctx.component.id, rerouted_def.clone());
+        for (&local, &id) in id_map.iter() {
+            self.hierarchy.insert(id, path.child(local));
+        }
 
         // Store the definition mapping to the ID
         self.definition_mapping.insert(ctx.component.id, ctx.component_def.id);
@@ -189,12 +226,73 @@ impl Circuit {
         for transparent in transparent_components {
             self.process_transparent(transparent)?;
         }
+        self.expansion_stack.pop();
 
         Ok(())
     }
 
+    /// Returns the internal nets of a transparent component.
+    ///
+    /// Each net is keyed by its driving connector local to the component's definition, and
+    /// holds the concrete connectors which actually drive it in the flattened circuit.
+    pub fn inner_nets(&self, path: &ComponentPath) -> Result<Vec<(Connector, Vec<Connector>)>, InspectionError> {
+        let id = self.hierarchy.id_of(path).ok_or_else(|| InspectionError::UnknownPath(path.clone()))?;
+        let rerouted_def = self.rerouted_defs.get(&id).ok_or_else(|| InspectionError::NotTransparent(path.clone()))?;
+        let connections = rerouted_def.circuit.as_ref().unwrap().connections.iter().map(|x| &x.from);
+        let outputs = rerouted_def.pin_mapping.as_ref().unwrap().output.iter().flatten();
+
+        let mut drivers: Vec<Connector> = connections.chain(outputs).copied().collect();
+        drivers.sort_by_key(|x| (x.component, x.pin));
+        drivers.dedup();
+
+        let mut nets = Vec::with_capacity(drivers.len());
+        for driver in drivers {
+            let local = self.hierarchy.path_of(driver.component).and_then(|x| x.local_id()).unwrap();
+            let concrete = self.reroute_to_concrete(driver).unwrap_or_default();
+            nets.push((Connector::new(local, driver.pin), concrete));
+        }
+        nets.sort_by_key(|(x, _)| (x.component, x.pin));
+
+        Ok(nets)
+    }
+
+    /// Allocates a flattened ID for every component inside the transparent component.
+    ///
+    /// Also validates that the definition only references its own components.
+    fn allocate_inner_ids(&mut self, component_def: &ComponentDefinition, path: &ComponentPath) -> Result<HashMap<Id, Id>, DefinitionError> {
+        let inner_circuit = component_def.circuit.as_ref().ok_or(InvalidTransparentComponent("No circuit field".into()))?;
+        let pin_mapping = component_def.pin_mapping.as_ref().ok_or(InvalidTransparentComponent("No pinMapping field".into()))?;
+
+        let mut id_map = HashMap::new();
+        for component in inner_circuit.components.iter() {
+            rassert!(!id_map.contains_key(&component.id), InvalidTransparentComponent(
+                format!("Component id {} is used more than once inside {}", component.id, path)
+            ));
+            let id = self.allocator.allocate().ok_or(InvalidTransparentComponent("Ran out of component IDs".into()))?;
+            id_map.insert(component.id, id);
+        }
+
+        let connections = inner_circuit.connections.iter().flat_map(|x| std::iter::once(&x.from).chain(x.to.iter()));
+        let mapped = pin_mapping.input.iter().chain(pin_mapping.output.iter()).flatten();
+        for &connector in connections.chain(mapped) {
+            rassert!(id_map.contains_key(&connector.component), InvalidInnerConnector { path: path.clone(), connector });
+        }
+
+        Ok(id_map)
+    }
+
+    /// Creates an invalid connector error, referring to the hierarchical path if the
+    /// connector is inside a transparent component.
+    fn invalid_connector(&self, connector: Connector) -> DefinitionError {
+        let path = self.hierarchy.path_of(connector.component);
+        match (path.and_then(|x| x.parent()), path.and_then(|x| x.local_id())) {
+            (Some(parent), Some(local)) => InvalidInnerConnector { path: parent, connector: Connector::new(local, connector.pin) },
+            _ => InvalidConnector(connector),
+        }
+    }
+
     /// Reroutes the connector to the first connected builtin component.
-    fn reroute_to_concrete(&self, connector: Connector) -> Result<Vec<Connector>, DefinitionError> {
+    pub(crate) fn reroute_to_concrete(&self, connector: Connector) -> Result<Vec<Connector>, DefinitionError> {
         let mut rerouted_connectors = Vec::new();
         self.reroute_to_concrete_impl(connector, &mut rerouted_connectors)?;
 
@@ -203,16 +301,15 @@ impl Circuit {
 
     /// Reroutes the connector to the first connected concrete component.
     fn reroute_to_concrete_impl(&self, connector: Connector, rerouted_connectors: &mut Vec<Connector>) -> Result<(), DefinitionError> {
-        let component = self.components.get(&connector.component).ok_or(InvalidConnector(connector))?;
+        rassert!(self.components.contains_key(&connector.component), self.invalid_connector(connector));
 
-        if let Some(_) = get_transparent(component) {
-            let rerouted_def = self.rerouted_defs.get(&connector.component).unwrap();
+        if let Some(rerouted_def) = self.rerouted_defs.get(&connector.component) {
             let pin_mapping = rerouted_def.pin_mapping.as_ref().unwrap();
             let input = pin_mapping.input.iter();
             let output = pin_mapping.output.iter();
             let mut pins = input.chain(output);
             
-            let connectors = pins.nth(connector.pin as usize).ok_or(InvalidConnector(connector))?;
+            let connectors = pins.nth(connector.pin as usize).ok_or_else(|| self.invalid_connector(connector))?;
             for connector in connectors {
                 self.reroute_to_concrete_impl(*connector, rerouted_connectors)?;
             }
@@ -265,16 +362,6 @@ impl Circuit {
     }
 }
 
-fn get_transparent(component: &Box<dyn Component>) -> Option<&Generic> {
-    if let Some(generic) = component.as_any().downcast_ref::<Generic>() {
-        if unsafe { (*generic.component_def).kind == ComponentKind::Transparent } {
-            return Some(generic);
-        }
-    }
-
-    None
-}
-
 #[derive(Debug)]
 struct Context<'a> {
     component: component::definition::Component,
@@ -296,13 +383,50 @@ pub enum DefinitionError {
 
     #[error("Invalid connector {0} found in circuit connections.")]
     InvalidConnector(Connector),
+
+    #[error("Invalid connector {connector} found inside component {path}.")]
+    InvalidInnerConnector {
+        path: ComponentPath,
+        connector: Connector,
+    },
+
+    #[error("Params are given for component {0}, which does not exist in the definition.")]
+    UnknownParamsComponent(u32),
+
+    #[error("Definition contains itself through the path {}.", format_cycle(.0))]
+    RecursiveDefinition(Vec<i32>),
+
+    #[error("Definition id {0} is used by more than one definition.")]
+    DuplicateDefinitionId(i32),
+
+    #[error("Definition id {0} is reserved for prebuilt definitions.")]
+    ReservedDefinitionId(i32),
+}
+
+fn format_cycle(cycle: &[i32]) -> String {
+    cycle.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(" -> ")
 }
 
 
 #[cfg(test)]
 mod tests {
     use crate::{component::ComponentDefinition, Circuit};
-    use super::{CircuitDefinition, Registry};
+    use crate::component::definition::Component;
+    use super::{CircuitDefinition, Registry, DefinitionError, ComponentPath, Connection, Connector, Id};
+
+    fn load_registry() -> Registry {
+        let mut registry = Registry::default();
+        for def in [
+            include_str!("../../tests/assets/and_gate_definition.json"),
+            include_str!("../../tests/assets/not_gate_definition.json"),
+            include_str!("../../tests/assets/ab_inverted_definition.json"),
+        ] {
+            let parsed: ComponentDefinition = serde_json::from_str(def).unwrap();
+            registry.try_insert(parsed).unwrap();
+        }
+
+        registry
+    }
 
     #[test]
     fn nand_gate() {
@@ -342,24 +466,156 @@ mod tests {
         let parsed: CircuitDefinition = serde_json::from_str(def).unwrap();
         let circuit = Circuit::from_definition(&registry, parsed).unwrap();
 
-        println!("AB inverted");
-        for (id, component) in circuit.components.iter() {
-            let rerouted_def = circuit.rerouted_defs.get(id);
-            if let Some(def) = rerouted_def {
-                println!("{}. Component: {:?}", id, component);
-                println!("{:?}", def.pin_mapping);
-                println!("{:?}", def.circuit.as_ref().unwrap().connections);
-            } else {
-                println!("{}. Component: {:?}", id, component);
-            }
+        // The output of the inverted AND reroutes straight to both inputs of the NOT gate's NAND,
+        // besides the top-level wiring
+        let id = |path: &str| circuit.hierarchy.id_of(&path.parse().unwrap()).unwrap();
+        let mut to = circuit.connections[&Connector::new(id("0/1/1"), 2)].clone();
+        to.retain(|x| x.component != Id::MAX);
+        to.sort_by_key(|x| (x.component, x.pin));
+        assert_eq!(to, vec![Connector::new(id("1/0"), 0), Connector::new(id("1/0"), 1)]);
+        assert_eq!(circuit.components.values().filter(|x| x.as_any().is::<crate::component::Nand>()).count(), 4);
+    }
 
+    #[test]
+    fn direct_recursion_rejected() {
+        let mut registry = load_registry();
+
+        let def = include_str!("../../tests/assets/not_gate_definition.json");
+        let mut parsed: ComponentDefinition = serde_json::from_str(def).unwrap();
+        parsed.circuit.as_mut().unwrap().components.push(Component { def_id: 2, id: 1 });
+
+        let result = registry.try_insert(parsed);
+        assert!(matches!(result, Err(DefinitionError::RecursiveDefinition(cycle)) if cycle == vec![2, 2]));
+    }
+
+    #[test]
+    fn indirect_recursion_rejected() {
+        let mut registry = load_registry();
+
+        // AND gate now uses the AB inverted component, which in turn uses the AND gate
+        let def = include_str!("../../tests/assets/and_gate_definition.json");
+        let mut parsed: ComponentDefinition = serde_json::from_str(def).unwrap();
+        parsed.circuit.as_mut().unwrap().components.push(Component { def_id: 3, id: 2 });
+
+        let result = registry.try_insert(parsed);
+        assert!(matches!(result, Err(DefinitionError::RecursiveDefinition(cycle)) if cycle == vec![1, 3, 1]));
+        assert!(registry.get_definition(1).unwrap().dependencies().all(|id| id == -1));
+    }
+
+    #[test]
+    fn recursive_circuit_fails_to_build() {
+        let mut registry = load_registry();
+
+        // Bypass the registry check to simulate a recursive registry
+        let def = include_str!("../../tests/assets/and_gate_definition.json");
+        let mut parsed: ComponentDefinition = serde_json::from_str(def).unwrap();
+        parsed.circuit.as_mut().unwrap().components.push(Component { def_id: 3, id: 2 });
+        registry.insert(parsed);
+
+        let def = include_str!("../../tests/assets/ab_inverted_on_not_circuit.json");
+        let parsed: CircuitDefinition = serde_json::from_str(def).unwrap();
+        let result = Circuit::from_definition(&registry, parsed);
+        assert!(matches!(result, Err(DefinitionError::RecursiveDefinition(cycle)) if cycle == vec![3, 1, 3]));
+    }
+
+    #[test]
+    fn flattened_ids_avoid_top_level_ids() {
+        let registry = load_registry();
+
+        // Sparse top-level IDs which used to collide with the flattened IDs
+        let def = include_str!("../../tests/assets/nand_gate_circuit.json");
+        let mut parsed: CircuitDefinition = serde_json::from_str(def).unwrap();
+        parsed.components[1].id = 2;
+        parsed.connections[0].to[0].component = 2;
+        let circuit = Circuit::from_definition(&registry, parsed).unwrap();
+
+        for path in ["0", "2", "0/0", "0/1", "2/0"] {
+            let path: ComponentPath = path.parse().unwrap();
+            let id = circuit.hierarchy.id_of(&path).unwrap();
+            assert!(circuit.components.contains_key(&id));
+            assert_eq!(circuit.hierarchy.path_of(id), Some(&path));
         }
+        assert_eq!(circuit.hierarchy.id_of(&ComponentPath::top_level(2)), Some(2));
+        assert_eq!(circuit.components.len(), 6);
+    }
+
+    #[test]
+    fn reserved_wiring_id_rejected() {
+        let registry = load_registry();
+
+        let def = include_str!("../../tests/assets/nand_gate_circuit.json");
+        let mut parsed: CircuitDefinition = serde_json::from_str(def).unwrap();
+        parsed.components[1].id = Id::MAX;
+        parsed.connections[0].to[0].component = Id::MAX;
+
+        let result = Circuit::from_definition(&registry, parsed);
+        assert!(matches!(result, Err(DefinitionError::ComponentIdAlreadyTaken(Id::MAX))));
+    }
+
+    #[test]
+    fn inner_errors_refer_to_path() {
+        let mut registry = load_registry();
+
+        let def = include_str!("../../tests/assets/not_gate_definition.json");
+        let mut parsed: ComponentDefinition = serde_json::from_str(def).unwrap();
+        parsed.circuit.as_mut().unwrap().connections.push(Connection {
+            from: Connector::new(0, 2),
+            to: vec![Connector::new(7, 0)],
+        });
+        registry.insert(parsed);
 
-        for (from, to) in circuit.connections.iter() {
-            println!("Connection: (from: {:?}, to: {:?})", from, to);
+        let def = include_str!("../../tests/assets/ab_inverted_on_not_circuit.json");
+        let parsed: CircuitDefinition = serde_json::from_str(def).unwrap();
+        let result = Circuit::from_definition(&registry, parsed);
+        match result {
+            Err(DefinitionError::InvalidInnerConnector { path, connector }) => {
+                assert_eq!(path.to_string(), "0/0");
+                assert_eq!(connector, Connector::new(7, 0));
+            },
+            _ => panic!("Expected an invalid inner connector error."),
         }
     }
-}
 
+    #[test]
+    fn round_trip() {
+        for def in [
+            include_str!("../../tests/assets/nand_gate_circuit.json"),
+            include_str!("../../tests/assets/ab_inverted_on_not_circuit.json"),
+        ] {
+            let expected: serde_json::Value = serde_json::from_str(def).unwrap();
+            let parsed: CircuitDefinition = serde_json::from_str(def).unwrap();
+            let serialized = serde_json::to_value(&parsed).unwrap();
+            assert_eq!(serialized, expected);
+        }
 
+        let registry = load_registry();
+        let serialized = serde_json::to_string(&registry).unwrap();
+        let reparsed: Registry = serde_json::from_str(&serialized).unwrap();
+        for id in [-7, -1, 1, 2, 3] {
+            assert_eq!(reparsed.get_definition(id).unwrap(), registry.get_definition(id).unwrap());
+        }
+    }
 
+    #[test]
+    fn registry_overlay() {
+        let base = std::sync::Arc::new(load_registry());
+        let mut overlay = Registry::overlay(base.clone());
+        let mut renamed = base.get_definition(1).unwrap().clone();
+        renamed.name = "Renamed".into();
+        let mut added = renamed.clone();
+        added.id = 4;
+        overlay.insert(renamed);
+        overlay.insert(added);
+
+        // Definitions of the overlay take precedence, without changing the shared registry
+        assert_eq!(overlay.get_definition(1).unwrap().name, "Renamed");
+        assert_eq!(base.get_definition(1).unwrap().name, "AndGate");
+        assert_eq!(overlay.get_definition(3).unwrap(), base.get_definition(3).unwrap());
+        assert!(base.get_definition(4).is_err());
+        assert_eq!(overlay.definitions().count(), base.definitions().count() + 1);
+
+        let reparsed: Registry = serde_json::from_str(&serde_json::to_string(&overlay).unwrap()).unwrap();
+        assert_eq!(reparsed.definitions().count(), overlay.definitions().count());
+        assert_eq!(reparsed.get_definition(1).unwrap().name, "Renamed");
+    }
+}
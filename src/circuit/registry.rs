@@ -1,26 +1,104 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use crate::{component::*, wasm};
 use crate::component::definition::{Pins, ComponentKind};
 use parking_lot::Mutex;
 
-use super::Params;
+use super::{Params, DefinitionError};
 
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct Registry {
     components: HashMap<i32, ComponentDefinition>,
+    /// Shared definitions which are looked up unless overridden by `components`.
+    #[serde(skip)]
+    base: Option<Arc<Registry>>,
 }
 
 impl Registry {
+    /// Creates an empty registry on top of the shared one, so that definitions can be added
+    /// without copying it.
+    pub fn overlay(base: Arc<Registry>) -> Self {
+        Self { components: HashMap::new(), base: Some(base) }
+    }
+
     pub fn replace(&mut self, other: Registry) {
         self.components = other.components;
+        self.base = other.base;
     }
 
     pub fn insert(&mut self, def: ComponentDefinition) {
         self.components.insert(def.id, def);
     }
 
+    /// Inserts the definition only if it does not (transitively) contain itself.
+    pub fn try_insert(&mut self, def: ComponentDefinition) -> Result<(), DefinitionError> {
+        if let Some(cycle) = self.find_cycle(&def) {
+            return Err(DefinitionError::RecursiveDefinition(cycle));
+        }
+
+        self.insert(def);
+        Ok(())
+    }
+
     pub fn get_definition(&self, id: i32) -> Result<&ComponentDefinition, RegistryError> {
-        self.components.get(&id).ok_or(RegistryError::InvalidDefinitionId(id))
+        match (self.components.get(&id), self.base.as_ref()) {
+            (Some(def), _) => Ok(def),
+            (None, Some(base)) => base.get_definition(id),
+            (None, None) => Err(RegistryError::InvalidDefinitionId(id)),
+        }
+    }
+
+    /// Iterates over all definitions in the registry, in no particular order.
+    pub fn definitions(&self) -> Box<dyn Iterator<Item = &ComponentDefinition> + '_> {
+        let base = self.base.iter()
+            .flat_map(|x| x.definitions())
+            .filter(|x| !self.components.contains_key(&x.id));
+        Box::new(self.components.values().chain(base))
+    }
+
+    /// Finds a cycle in the definition dependency graph reachable from the given definition.
+    ///
+    /// The definition is considered as if it had already replaced the registry entry with the
+    /// same ID. Unknown definition IDs are skipped since they are reported when building.
+    ///
+    /// # Returns
+    /// The cycle path, starting and ending with the same definition ID.
+    pub fn find_cycle(&self, def: &ComponentDefinition) -> Option<Vec<i32>> {
+        let mut path = Vec::new();
+        let mut visited = HashSet::new();
+
+        self.find_cycle_impl(def, def, &mut path, &mut visited)
+    }
+
+    fn find_cycle_impl(&self, root: &ComponentDefinition, def: &ComponentDefinition, path: &mut Vec<i32>, visited: &mut HashSet<i32>) -> Option<Vec<i32>> {
+        if let Some(pos) = path.iter().position(|&id| id == def.id) {
+            let mut cycle = path[pos..].to_vec();
+            cycle.push(def.id);
+            return Some(cycle);
+        }
+
+        // Subgraphs which were fully explored before cannot contain new cycles
+        if !visited.insert(def.id) {
+            return None;
+        }
+
+        path.push(def.id);
+        for dependency in def.dependencies() {
+            let dependency = if dependency == root.id {
+                root
+            } else if let Ok(dependency) = self.get_definition(dependency) {
+                dependency
+            } else {
+                continue;
+            };
+
+            if let Some(cycle) = self.find_cycle_impl(root, dependency, path, visited) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+
+        None
     }
 }
 
@@ -41,10 +119,23 @@ impl Default for Registry {
         
         Self {
             components,
+            base: None,
         }
     }
 }
 
+/// Serializes the definitions of the shared registry too, in the same format as it is read.
+impl serde::Serialize for Registry {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(serde::Serialize)]
+        struct Document<'a> {
+            components: HashMap<i32, &'a ComponentDefinition>,
+        }
+
+        Document { components: self.definitions().map(|x| (x.id, x)).collect() }.serialize(serializer)
+    }
+}
+
 pub struct PrebuiltRegistry {
     pub data: HashMap<i32, PrebuiltEntry>,
 }
@@ -66,9 +157,23 @@ pub fn set_registry(registry: wasm::JsValue) {
 }
 
 #[wasm::wasm_bindgen]
-pub fn update_registry(definition: wasm::JsValue) {
+pub fn update_registry(definition: wasm::JsValue) -> Result<(), String> {
     let component_def = definition.into_serde().expect("Expected the component definition to be in correct format");
-    REGISTRY.with(|reg| reg.lock().insert(component_def))
+    REGISTRY.with(|reg| reg.lock().try_insert(component_def)).map_err(|e| e.to_string())
+}
+
+#[wasm::wasm_bindgen]
+pub fn get_registry() -> wasm::JsValue {
+    REGISTRY.with(|reg| wasm::JsValue::from_serde(&*reg.lock()).unwrap())
+}
+
+#[wasm::wasm_bindgen]
+pub fn get_definition(id: i32) -> Result<wasm::JsValue, String> {
+    REGISTRY.with(|reg| {
+        let reg = reg.lock();
+        let component_def = reg.get_definition(id).map_err(|e| e.to_string())?;
+        Ok(wasm::JsValue::from_serde(component_def).unwrap())
+    })
 }
 
 // Prebuilt IDs
@@ -79,6 +184,7 @@ pub const GROUND_ID: i32 = -4;
 pub const SOURCE_ID: i32 = -5;
 pub const SWITCH_ID: i32 = -6;
 pub const LED_ID: i32 = -7;
+pub const BUTTON_ID: i32 = -8;
 
 impl Default for PrebuiltRegistry {
     fn default() -> Self {
@@ -225,6 +331,26 @@ impl Default for PrebuiltRegistry {
             factory: Box::new(|params| Box::new(Led::from_params(params))),
         });
 
+        // Button
+        data.insert(-8, PrebuiltEntry {
+            def: ComponentDefinition {
+                id: -8,
+                name: "Button".into(),
+                desc: "User-input component which emits 1 only while it is pressed.".into(),
+                kind: ComponentKind::Builtin,
+                pins: Pins {
+                    input: vec![],
+                    output: vec!["Y".into()],
+                },
+                pin_mapping: None,
+                circuit: None,
+                truth_table: None,
+                expr: None,
+                parsed_expr: None,
+            },
+            factory: Box::new(|params| Box::new(Button::from_params(params))),
+        });
+
         Self {
             data,
         }
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use super::Id;
+use super::{Id, ComponentPath};
 use crate::wasm;
 
 #[derive(Debug, Default)]
@@ -13,3 +13,18 @@ impl CircuitState {
     }
 }
 
+
+#[derive(Debug, thiserror::Error)]
+pub enum InspectionError {
+    #[error("No component found at path {0}.")]
+    UnknownPath(ComponentPath),
+
+    #[error("Component at path {0} is not a transparent component.")]
+    NotTransparent(ComponentPath),
+
+    #[error("Component at path {0} has no pin {1}.")]
+    InvalidPin(ComponentPath, u32),
+
+    #[error("Inspection is not enabled for the simulation.")]
+    NotEnabled,
+}
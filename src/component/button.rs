@@ -0,0 +1,99 @@
+use std::any::Any;
+
+use crate::circuit::{Connector, Params};
+use super::Component;
+use crate::sim::{Event, UserEvent, UserEventError};
+use UserEventError::*;
+
+/// Number of ticks a pulse stays high unless set by the `pulseWidth` param.
+const DEFAULT_PULSE_WIDTH: u32 = 10;
+
+/// Momentary user-input component, which is high only while pressed.
+#[derive(Debug, Clone, Default)]
+pub struct Button {
+    pub(crate) output: bool,
+
+    delay: u32,
+    pulse_width: u32,
+}
+
+impl Component for Button {
+    fn evaluate(&self) -> Option<Vec<(u32, bool)>> {
+        Some(vec![(0, self.output)])
+    }
+
+    fn update(&mut self, event: Event) {
+        if event.src.pin == 0 {
+            self.output = event.value;
+        }
+    }
+
+    fn set_pin(&mut self, _pin: u32, _event: Event) {
+        // set_pin is not implemented for source components
+        unreachable!()
+    }
+
+    fn get_state(&self) -> serde_json::Value {
+        unimplemented!("Button does not implement get_state since it is not an output component.");
+    }
+
+    fn delay(&self) -> u32 {
+        self.delay
+    }
+
+    fn is_source(&self) -> bool {
+        true
+    }
+
+    fn is_output(&self) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    /// Presses or releases the button, or presses it and schedules its release after the pulse
+    /// width.
+    fn process_user_event(&self, user_event: UserEvent) -> Result<Vec<(u32, Event)>, UserEventError> {
+        let src = Connector { component: user_event.component_id, pin: 0 };
+        match user_event.payload.as_str() {
+            Some("press") => Ok(vec![(self.delay, Event::new(true, src))]),
+            Some("release") => Ok(vec![(self.delay, Event::new(false, src))]),
+            Some("pulse") => Ok(vec![
+                (self.delay, Event::new(true, src)),
+                (self.delay + self.pulse_width, Event::new(false, src)),
+            ]),
+            _ => Err(InvalidPayload("Button only receives the messages 'press', 'release' and 'pulse'.".into())),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.output = false;
+    }
+}
+
+impl Button {
+    pub fn from_params(params: Params) -> Self {
+        let delay = if let Some(param) = params.get("delay") {
+            param.as_u64().unwrap() as u32
+        } else {
+            1
+        };
+        let pulse_width = if let Some(param) = params.get("pulseWidth") {
+            param.as_u64().unwrap() as u32
+        } else {
+            DEFAULT_PULSE_WIDTH
+        };
+
+        Self {
+            delay,
+            pulse_width,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::circuit::{Connection, Connector, Id};
+use crate::circuit::registry::{NAND_ID, TRISTATE_ID, GROUND_ID, SOURCE_ID};
+use super::{Circuit, Component, ComponentDefinition, ComponentKind, PinMapping, Pins};
+
+/// A signal inside a transparent definition under construction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Signal {
+    /// The definition's input pin.
+    Input(usize),
+    /// An output connector of an inner component.
+    Output(Connector),
+    /// A named net whose drivers may be bound later, e.g. for feedback loops.
+    Net(usize),
+}
+
+/// Incrementally builds a transparent component definition out of inner components.
+///
+/// Gates other than NAND are decomposed into NAND gates.
+#[derive(Debug, Default)]
+pub struct TransparentBuilder {
+    components: Vec<Component>,
+    sinks: Vec<(Signal, Connector)>,
+    nets: Vec<Vec<Signal>>,
+    inverted: HashMap<Signal, Signal>,
+    constants: HashMap<bool, Signal>,
+}
+
+impl TransparentBuilder {
+    /// Adds an inner component and connects its inputs.
+    ///
+    /// # Returns
+    /// The signals of the component's outputs.
+    pub fn component(&mut self, def_id: i32, inputs: &[Signal], num_outputs: usize) -> Vec<Signal> {
+        let id = self.components.len() as Id;
+        self.components.push(Component { def_id, id });
+        for (pin, &input) in inputs.iter().enumerate() {
+            self.sinks.push((input, Connector::new(id, pin as Id)));
+        }
+
+        (0..num_outputs).map(|x| Signal::Output(Connector::new(id, (inputs.len() + x) as Id))).collect()
+    }
+
+    /// Declares a net without drivers.
+    pub fn net(&mut self) -> Signal {
+        self.nets.push(Vec::new());
+        Signal::Net(self.nets.len() - 1)
+    }
+
+    /// Adds a driver to a net previously declared with `net`.
+    pub fn drive(&mut self, net: Signal, driver: Signal) {
+        if let Signal::Net(net) = net {
+            self.nets[net].push(driver);
+        }
+    }
+
+    pub fn nand(&mut self, a: Signal, b: Signal) -> Signal {
+        self.component(NAND_ID, &[a, b], 1)[0]
+    }
+
+    pub fn tristate(&mut self, a: Signal, enable: Signal) -> Signal {
+        self.component(TRISTATE_ID, &[a, enable], 1)[0]
+    }
+
+    /// Returns a constant signal, driven by a Source or a Ground component.
+    pub fn constant(&mut self, value: bool) -> Signal {
+        if let Some(&signal) = self.constants.get(&value) {
+            return signal;
+        }
+
+        let signal = self.component(if value { SOURCE_ID } else { GROUND_ID }, &[], 1)[0];
+        self.constants.insert(value, signal);
+        signal
+    }
+
+    pub fn not(&mut self, a: Signal) -> Signal {
+        if let Some(&signal) = self.inverted.get(&a) {
+            return signal;
+        }
+
+        let signal = self.nand(a, a);
+        self.inverted.insert(a, signal);
+        self.inverted.insert(signal, a);
+        signal
+    }
+
+    pub fn and(&mut self, inputs: &[Signal]) -> Signal {
+        let (&first, rest) = inputs.split_first().expect("Expected at least one input.");
+        rest.iter().fold(first, |acc, &x| {
+            let nand = self.nand(acc, x);
+            self.not(nand)
+        })
+    }
+
+    pub fn or(&mut self, inputs: &[Signal]) -> Signal {
+        let (&first, rest) = inputs.split_first().expect("Expected at least one input.");
+        rest.iter().fold(first, |acc, &x| {
+            let a = self.not(acc);
+            let b = self.not(x);
+            self.nand(a, b)
+        })
+    }
+
+    pub fn xor(&mut self, inputs: &[Signal]) -> Signal {
+        let (&first, rest) = inputs.split_first().expect("Expected at least one input.");
+        rest.iter().fold(first, |acc, &x| {
+            let nand = self.nand(acc, x);
+            let a = self.nand(acc, nand);
+            let b = self.nand(x, nand);
+            self.nand(a, b)
+        })
+    }
+
+    /// Resolves a signal to the concrete signals driving it.
+    fn resolve(&self, signal: Signal, resolved: &mut Vec<Signal>, visiting: &mut Vec<usize>) {
+        match signal {
+            Signal::Net(net) => {
+                // Nets only aliasing each other are left undriven
+                if visiting.contains(&net) {
+                    return;
+                }
+
+                visiting.push(net);
+                for &driver in self.nets[net].iter() {
+                    self.resolve(driver, resolved, visiting);
+                }
+                visiting.pop();
+            },
+            signal => {
+                if !resolved.contains(&signal) {
+                    resolved.push(signal);
+                }
+            },
+        }
+    }
+
+    /// Builds the transparent component definition.
+    ///
+    /// Outputs driven directly by inputs are buffered by two NAND gates, while inputs of
+    /// undriven nets are left unconnected. NAND gates whose outputs are unused are removed.
+    pub fn build(mut self, id: i32, name: String, pins: Pins, outputs: &[Signal]) -> ComponentDefinition {
+        debug_assert_eq!(pins.output.len(), outputs.len());
+
+        let mut output_drivers = Vec::with_capacity(outputs.len());
+        for &output in outputs {
+            let mut drivers = Vec::new();
+            self.resolve(output, &mut drivers, &mut Vec::new());
+
+            let drivers: Vec<Connector> = drivers.into_iter()
+                .map(|driver| {
+                    let driver = match driver {
+                        Signal::Input(_) => {
+                            let inverted = self.not(driver);
+                            self.nand(inverted, inverted)
+                        },
+                        driver => driver,
+                    };
+                    match driver {
+                        Signal::Output(connector) => connector,
+                        _ => unreachable!(),
+                    }
+                })
+                .collect();
+            output_drivers.push(drivers);
+        }
+
+        // Group sinks by their concrete drivers, keeping the insertion order
+        let mut pin_mapping = PinMapping {
+            input: vec![Vec::new(); pins.input.len()],
+            output: output_drivers,
+        };
+        let mut connections: Vec<Connection> = Vec::new();
+        let mut connection_index = HashMap::new();
+        for &(signal, sink) in self.sinks.iter() {
+            let mut drivers = Vec::new();
+            self.resolve(signal, &mut drivers, &mut Vec::new());
+
+            for driver in drivers {
+                match driver {
+                    Signal::Input(input) => pin_mapping.input[input].push(sink),
+                    Signal::Output(from) => {
+                        let index = *connection_index.entry(from).or_insert_with(|| {
+                            connections.push(Connection { from, to: Vec::new() });
+                            connections.len() - 1
+                        });
+                        connections[index].to.push(sink);
+                    },
+                    Signal::Net(_) => unreachable!(),
+                }
+            }
+        }
+
+        let def = ComponentDefinition {
+            id,
+            name,
+            desc: String::new(),
+            kind: ComponentKind::Transparent,
+            pins,
+            pin_mapping: Some(pin_mapping),
+            circuit: Some(Circuit {
+                components: self.components,
+                connections,
+                params: None,
+            }),
+            truth_table: None,
+            expr: None,
+            parsed_expr: None,
+        };
+        prune_unused_gates(def)
+    }
+}
+
+/// Removes NAND gates whose outputs are never used, e.g. inverters made redundant by `not`.
+fn prune_unused_gates(mut def: ComponentDefinition) -> ComponentDefinition {
+    let circuit = def.circuit.as_mut().unwrap();
+    let pin_mapping = def.pin_mapping.as_mut().unwrap();
+
+    let mut live: HashSet<Id> = circuit.components.iter().filter(|x| x.def_id != NAND_ID).map(|x| x.id).collect();
+    live.extend(pin_mapping.output.iter().flatten().map(|x| x.component));
+    loop {
+        let before = live.len();
+        for connection in circuit.connections.iter() {
+            if connection.to.iter().any(|x| live.contains(&x.component)) {
+                live.insert(connection.from.component);
+            }
+        }
+
+        if live.len() == before {
+            break;
+        }
+    }
+
+    if live.len() == circuit.components.len() {
+        return def;
+    }
+
+    circuit.components.retain(|x| live.contains(&x.id));
+    circuit.connections.retain(|x| live.contains(&x.from.component));
+    circuit.connections.iter_mut().for_each(|x| x.to.retain(|to| live.contains(&to.component)));
+    circuit.connections.retain(|x| !x.to.is_empty());
+    pin_mapping.input.iter_mut().for_each(|x| x.retain(|to| live.contains(&to.component)));
+
+    // Renumber the remaining components consecutively
+    let ids: HashMap<Id, Id> = circuit.components.iter().enumerate().map(|(i, x)| (x.id, i as Id)).collect();
+    def.remap_component_def(|id| ids[&id]).expect("Built definitions have no params")
+}
@@ -3,9 +3,10 @@ use std::collections::HashMap;
 use crate::circuit::{Connection, Id, Params};
 use super::Component;
 
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, serde::Serialize)]
 pub struct Circuit {
     pub components: Vec<Component>,
     pub connections: Vec<Connection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub params: Option<HashMap<Id, Params>>,
 }
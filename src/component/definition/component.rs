@@ -1,6 +1,6 @@
 
 
-#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, serde::Deserialize)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, serde::Deserialize, serde::Serialize)]
 pub struct Component {
     #[serde(rename = "definitionId")] pub def_id: i32,
     pub id: u32,
@@ -4,20 +4,23 @@ mod pin_mapping;
 mod circuit;
 mod component;
 mod truth_table;
+mod builder;
 pub use kind::ComponentKind;
 pub use pins::Pins;
 pub use pin_mapping::PinMapping;
 pub use circuit::Circuit;
 pub use component::Component;
-pub use truth_table::TruthTable;
+pub use truth_table::{TruthTable, MAX_TABLE_INPUTS};
+pub use builder::{Signal, TransparentBuilder};
 
+use std::collections::HashSet;
 use super::Component as ComponentTrait;
 use derivative::Derivative;
-use crate::circuit::Params;
+use crate::circuit::{DefinitionError, Id, Params};
 use crate::circuit::registry::PREBUILT_REGISTRY;
 use crate::component::Generic;
 
-#[derive(Derivative, Debug, Clone, serde::Deserialize)]
+#[derive(Derivative, Debug, Clone, serde::Deserialize, serde::Serialize)]
 #[derivative(PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ComponentDefinition {
@@ -28,10 +31,13 @@ pub struct ComponentDefinition {
     #[serde(rename = "type")] 
     pub kind: ComponentKind,
     pub pins: Pins,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub pin_mapping: Option<PinMapping>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub circuit: Option<Circuit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub truth_table: Option<TruthTable>,
-    #[serde(rename = "booleanFunction")] 
+    #[serde(rename = "booleanFunction", skip_serializing_if = "Option::is_none")] 
     pub expr: Option<String>,
 
     #[serde(skip)]
@@ -48,32 +54,49 @@ impl ComponentDefinition {
         }
     }
 
+    /// Returns the definition IDs of all components used inside the definition's circuit.
+    pub fn dependencies(&self) -> impl Iterator<Item = i32> + '_ {
+        self.circuit.iter().flat_map(|circuit| circuit.components.iter().map(|x| x.def_id))
+    }
+
     /// Reroutes transparent component definition's IDs into the current circuit.
-    pub fn reroute_component_def(&self, first_free_id: u32) -> Self {
+    pub fn reroute_component_def(&self, first_free_id: u32) -> Result<Self, DefinitionError> {
+        self.remap_component_def(|id| id + first_free_id)
+    }
+
+    /// Remaps transparent component definition's IDs through the given function.
+    ///
+    /// Fails with [`DefinitionError::UnknownParamsComponent`] if parameters are given for a
+    /// component which does not exist in the definition.
+    pub fn remap_component_def(&self, map: impl Fn(Id) -> Id) -> Result<Self, DefinitionError> {
         let mut new_component_def = self.clone();
         let circuit = new_component_def.circuit.as_mut().unwrap();
 
+        // Update params field
+        if let Some(params) = circuit.params.as_mut() {
+            let ids: HashSet<Id> = circuit.components.iter().map(|x| x.id).collect();
+            if let Some(&id) = params.keys().find(|id| !ids.contains(id)) {
+                return Err(DefinitionError::UnknownParamsComponent(id));
+            }
+            *params = params.drain().map(|(id, params_)| (map(id), params_)).collect();
+        }
+
         // Update all components' IDs
-        circuit.components.iter_mut().for_each(|x| x.id += first_free_id);
+        circuit.components.iter_mut().for_each(|x| x.id = map(x.id));
         
         // Update component IDs of all connections
         for connection in circuit.connections.iter_mut() {
             // Update 'from' connector and all 'to' connectors
-            connection.from.component += first_free_id;
-            connection.to.iter_mut().for_each(|x| x.component += first_free_id);
+            connection.from.component = map(connection.from.component);
+            connection.to.iter_mut().for_each(|x| x.component = map(x.component));
         }
         
         // Update input/output mapping
         let pin_mapping = new_component_def.pin_mapping.as_mut().unwrap();
-        pin_mapping.input.iter_mut().flatten().for_each(|x| x.component += first_free_id);
-        pin_mapping.output.iter_mut().flatten().for_each(|x| x.component += first_free_id);
-
-        // Update params field
-        if let Some(params) = circuit.params.as_mut() {
-            *params = params.drain().map(|(id, params_)| (id + first_free_id, params_)).collect();
-        }
+        pin_mapping.input.iter_mut().flatten().for_each(|x| x.component = map(x.component));
+        pin_mapping.output.iter_mut().flatten().for_each(|x| x.component = map(x.component));
 
-        new_component_def
+        Ok(new_component_def)
     }
 }
 
@@ -135,6 +158,7 @@ mod tests {
                     vec![false],
                     vec![true],
                 ],
+                unstable: Vec::new(),
             }),
             expr: Some("A and B".into()),
             parsed_expr: None,
@@ -186,7 +210,7 @@ mod tests {
             parsed_expr: None,
         };
 
-        let new_def = def.reroute_component_def(5);
+        let new_def = def.reroute_component_def(5).unwrap();
 
         let result = ComponentDefinition {
             id: 1,
@@ -231,5 +255,34 @@ mod tests {
         
         assert_eq!(new_def, result);
     }
-}
 
+    #[test]
+    fn reroute_params() {
+        let mut def: ComponentDefinition = serde_json::from_str(include_str!("../../../tests/assets/and_gate_definition.json")).unwrap();
+        let delay: Params = [("delay".to_string(), serde_json::json!(3))].into();
+        def.circuit.as_mut().unwrap().params = Some([(1, delay.clone())].into());
+        let rerouted = def.reroute_component_def(5).unwrap();
+        assert_eq!(rerouted.circuit.unwrap().params, Some([(6, delay.clone())].into()));
+
+        // Params of a component which is not in the definition are an error, not dropped
+        def.circuit.as_mut().unwrap().params = Some([(1, delay.clone()), (7, delay)].into());
+        assert!(matches!(def.reroute_component_def(5), Err(DefinitionError::UnknownParamsComponent(7))));
+    }
+
+    #[test]
+    fn round_trip() {
+        for def in [
+            include_str!("../../../tests/assets/and_gate_definition.json"),
+            include_str!("../../../tests/assets/not_gate_definition.json"),
+            include_str!("../../../tests/assets/ab_inverted_definition.json"),
+        ] {
+            let expected: serde_json::Value = serde_json::from_str(def).unwrap();
+            let parsed: ComponentDefinition = serde_json::from_str(def).unwrap();
+            let serialized = serde_json::to_value(&parsed).unwrap();
+            assert_eq!(serialized, expected);
+
+            let reparsed: ComponentDefinition = serde_json::from_value(serialized).unwrap();
+            assert_eq!(reparsed, parsed);
+        }
+    }
+}
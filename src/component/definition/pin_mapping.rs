@@ -1,6 +1,6 @@
 use crate::circuit::Connector;
 
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, serde::Serialize)]
 pub struct PinMapping {
     pub input: Vec<Vec<Connector>>,
     pub output: Vec<Vec<Connector>>,
@@ -1,4 +1,4 @@
-#[derive(Debug, PartialEq, Clone, serde::Deserialize)]
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, serde::Serialize)]
 pub struct Pins {
     pub input: Vec<String>,
     pub output: Vec<String>,
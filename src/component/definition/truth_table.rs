@@ -1,12 +1,49 @@
 use std::iter::Zip;
+use std::sync::Arc;
+use rassert_rs::rassert;
 
-#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+use crate::circuit::Registry;
+use crate::validation::{ConversionError, TestHarness, MAX_SETTLE_TICKS};
+use super::ComponentDefinition;
+
+/// The largest number of inputs a truth table is generated for.
+pub const MAX_TABLE_INPUTS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct TruthTable {
     pub inputs: Vec<Vec<bool>>,
     pub outputs: Vec<Vec<bool>>,
+    /// Indices of the rows whose outputs oscillate or never settle.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unstable: Vec<usize>,
 }
 
 impl TruthTable {
+    /// Generates the truth table of a transparent definition by simulating every input
+    /// combination, with the first input as the most significant bit.
+    ///
+    /// Rows which do not settle within [`MAX_SETTLE_TICKS`] are marked as unstable, with the
+    /// outputs they had at that point.
+    pub fn from_definition(registry: &Registry, component_def: &ComponentDefinition) -> Result<Self, ConversionError> {
+        let num_inputs = component_def.pins.input.len();
+        rassert!(num_inputs <= MAX_TABLE_INPUTS, ConversionError::TooManyInputs(num_inputs));
+
+        let mut harness = TestHarness::new(&Arc::new(registry.clone()), component_def.clone())?;
+        let mut table = TruthTable { inputs: Vec::new(), outputs: Vec::new(), unstable: Vec::new() };
+        for row in 0..1usize << num_inputs {
+            let inputs: Vec<bool> = (0..num_inputs).map(|i| row & (1 << (num_inputs - 1 - i)) != 0).collect();
+            harness.apply(&inputs);
+            if harness.settle(MAX_SETTLE_TICKS).is_none() {
+                table.unstable.push(row);
+            }
+
+            table.inputs.push(inputs);
+            table.outputs.push(harness.outputs());
+        }
+
+        Ok(table)
+    }
+
     pub fn iter(&self) -> Zip<std::slice::Iter<Vec<bool>>, std::slice::Iter<Vec<bool>>> {
         self.inputs.iter().zip(self.outputs.iter())
     }
@@ -16,3 +53,51 @@ impl TruthTable {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::Connector;
+    use crate::circuit::registry::NAND_ID;
+    use crate::component::definition::{Component, Pins, Signal, TransparentBuilder};
+
+    fn pins(inputs: &[&str], outputs: &[&str]) -> Pins {
+        Pins {
+            input: inputs.iter().map(|x| x.to_string()).collect(),
+            output: outputs.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn generated_tables() {
+        let registry = Registry::default();
+        let def: ComponentDefinition = serde_json::from_str(include_str!("../../../tests/assets/and_gate_definition.json")).unwrap();
+        let table = TruthTable::from_definition(&registry, &def).unwrap();
+        assert_eq!(Some(table), def.truth_table);
+
+        // The second input drives a gate whose output is left unconnected
+        let mut builder = TransparentBuilder::default();
+        let inverted = builder.not(Signal::Input(0));
+        let mut def = builder.build(1, "Unused".into(), pins(&["A", "B"], &["Y"]), &[inverted]);
+        def.circuit.as_mut().unwrap().components.push(Component { def_id: NAND_ID, id: 1 });
+        def.pin_mapping.as_mut().unwrap().input[1].push(Connector::new(1, 0));
+        let table = TruthTable::from_definition(&registry, &def).unwrap();
+        assert_eq!(table.outputs, vec![vec![true], vec![true], vec![false], vec![false]]);
+        assert!(table.unstable.is_empty());
+    }
+
+    #[test]
+    fn oscillating_rows() {
+        // A NAND gate feeding back into itself oscillates while its other input is set
+        let mut builder = TransparentBuilder::default();
+        let feedback = builder.net();
+        let output = builder.nand(Signal::Input(0), feedback);
+        builder.drive(feedback, output);
+        let def = builder.build(1, "Oscillator".into(), pins(&["Enable"], &["Y"]), &[output]);
+
+        let table = TruthTable::from_definition(&Registry::default(), &def).unwrap();
+        assert_eq!(table.inputs, vec![vec![false], vec![true]]);
+        assert_eq!(table.outputs[0], vec![true]);
+        assert_eq!(table.unstable, vec![1]);
+    }
+}
@@ -7,6 +7,12 @@ pub struct Led {
     pub(crate) value: bool,
 }
 
+impl Led {
+    pub fn value(&self) -> bool {
+        self.value
+    }
+}
+
 impl Component for Led {
     fn evaluate(&self) -> Option<Vec<(u32, bool)>> {
         None
@@ -4,6 +4,7 @@ mod nand;
 mod generic;
 mod wiring;
 mod switch;
+mod button;
 mod ground;
 mod source;
 mod clock;
@@ -16,6 +17,7 @@ pub use nand::Nand;
 pub use generic::Generic;
 pub use wiring::Wiring;
 pub use switch::Switch;
+pub use button::Button;
 pub use ground::Ground;
 pub use source::Source;
 pub use clock::Clock;
@@ -59,8 +61,8 @@ pub trait Component: Any + Debug {
     /// Resets the component's state to its default.
     fn reset(&mut self);
 
-    /// Processes a user event.
-    fn process_user_event(&self, _user_event: UserEvent) -> Result<Vec<Event>, UserEventError> {
+    /// Processes a user event, returning the events to schedule together with their delays.
+    fn process_user_event(&self, _user_event: UserEvent) -> Result<Vec<(u32, Event)>, UserEventError> {
         unimplemented!()
     }
 }
@@ -54,12 +54,12 @@ impl Component for Switch {
         self
     }
 
-	fn process_user_event(&self, user_event: UserEvent) -> Result<Vec<Event>, UserEventError> {
+	fn process_user_event(&self, user_event: UserEvent) -> Result<Vec<(u32, Event)>, UserEventError> {
         rassert!(user_event.payload.is_string() && user_event.payload.as_str().unwrap() == "toggle", 
                  InvalidPayload("Switch only receives the message 'toggle'.".into()));
 
 		let src = Connector { component: user_event.component_id, pin: 0 };
-        Ok(vec![Event::new(!self.output, src)])
+        Ok(vec![(self.delay, Event::new(!self.output, src))])
 	}
 
     fn reset(&mut self) {
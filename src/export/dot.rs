@@ -0,0 +1,309 @@
+use std::fmt::Write;
+
+use crate::Circuit;
+use crate::circuit::{CircuitDefinition, Connector, DefinitionError, Id, Registry};
+use crate::circuit::registry::PREBUILT_REGISTRY;
+use crate::component::ComponentDefinition;
+use crate::component::definition::{ComponentKind, Pins};
+use super::{builtin_id, ExportError};
+
+/// Exports the flattened circuit as a Graphviz DOT graph.
+///
+/// Only concrete components are drawn, connected the way they are after rerouting. Nodes are
+/// labelled with the components' hierarchical paths and edges with the connected pin names.
+pub fn circuit_to_dot(circuit: &Circuit, graph_name: &str) -> String {
+    let mut out = String::new();
+    header(&mut out, graph_name);
+
+    let mut ids: Vec<Id> = circuit.components.iter()
+        .filter(|(_, component)| builtin_id(component.as_ref()).is_some())
+        .map(|(id, _)| *id)
+        .collect();
+    ids.sort_by_key(|&id| circuit.hierarchy.path_of(id).map(|x| x.0.clone()).unwrap_or_else(|| vec![id]));
+
+    let definition = |id: Id| {
+        let def_id = builtin_id(circuit.components[&id].as_ref()).unwrap();
+        PREBUILT_REGISTRY.with(|reg| {
+            let def = &reg.data[&def_id].def;
+            (def.name.clone(), def.pins.clone())
+        })
+    };
+
+    let path = |id: Id| circuit.hierarchy.path_of(id).map_or_else(|| id.to_string(), |x| x.to_string());
+    for &id in ids.iter() {
+        writeln!(out, "    \"{}\" [label=\"{}: {}\"];", path(id), path(id), escape(&definition(id).0)).unwrap();
+    }
+
+    let position = |id: &Id| ids.iter().position(|x| x == id);
+    let mut edges: Vec<(&Connector, &Connector)> = circuit.connections.iter()
+        .flat_map(|(from, to)| to.iter().map(move |x| (from, x)))
+        .filter(|(from, to)| ids.contains(&from.component) && ids.contains(&to.component))
+        .collect();
+    edges.sort_by_key(|(from, to)| (position(&from.component), from.pin, position(&to.component), to.pin));
+    for (from, to) in edges {
+        writeln!(
+            out,
+            "    \"{}\" -> \"{}\" [taillabel=\"{}\", headlabel=\"{}\"];",
+            path(from.component),
+            path(to.component),
+            escape(&pin_name(&definition(from.component).1, from.pin)),
+            escape(&pin_name(&definition(to.component).1, to.pin)),
+        ).unwrap();
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// Exports the circuit definition as a Graphviz DOT graph, keeping its hierarchy.
+///
+/// Every transparent component becomes a cluster containing its inner components, with a node
+/// for each of its pins. Nodes are named after the components' hierarchical paths.
+pub fn circuit_definition_to_dot(registry: &Registry, circuit_def: &CircuitDefinition) -> Result<String, ExportError> {
+    let mut out = String::new();
+    header(&mut out, &circuit_def.name);
+
+    let components: Vec<(Id, i32)> = circuit_def.components.iter().map(|x| (x.id, x.def_id)).collect();
+    let connections: Vec<(Connector, &[Connector])> = circuit_def.connections.iter().map(|x| (x.from, x.to.as_slice())).collect();
+    Graph { registry, out: &mut out, stack: Vec::new() }.scope("", &components, &connections, 1)?;
+
+    out.push_str("}\n");
+    Ok(out)
+}
+
+/// An edge between two nodes, with the pin labels of both ends.
+type Edge = (String, Option<String>, String, Option<String>);
+
+struct Graph<'a> {
+    registry: &'a Registry,
+    out: &'a mut String,
+    /// Definition IDs of the transparent components currently being drawn
+    stack: Vec<i32>,
+}
+
+impl Graph<'_> {
+    /// Draws the components and connections of a single level of the hierarchy.
+    fn scope(&mut self, prefix: &str, components: &[(Id, i32)], connections: &[(Connector, &[Connector])], depth: usize) -> Result<(), ExportError> {
+        let indent = "    ".repeat(depth);
+
+        for &(id, def_id) in components {
+            let def = self.registry.get_definition(def_id)?;
+            let path = format!("{}{}", prefix, id);
+            if def.kind != ComponentKind::Transparent {
+                writeln!(self.out, "{}\"{}\" [label=\"{}: {}\"];", indent, path, id, escape(&def.name)).unwrap();
+                continue;
+            }
+
+            if self.stack.contains(&def_id) {
+                let mut cycle = self.stack.clone();
+                cycle.push(def_id);
+                return Err(DefinitionError::RecursiveDefinition(cycle).into());
+            }
+            self.stack.push(def_id);
+
+            writeln!(self.out, "{}subgraph \"cluster_{}\" {{", indent, path).unwrap();
+            writeln!(self.out, "{}    label=\"{}: {}\";", indent, id, escape(&def.name)).unwrap();
+            for (pin, name) in def.pins.input.iter().chain(def.pins.output.iter()).enumerate() {
+                writeln!(self.out, "{}    \"{}\" [label=\"{}\", shape=plaintext];", indent, pin_node(&path, pin as Id), escape(name)).unwrap();
+            }
+
+            let inner = def.circuit.as_ref().ok_or(DefinitionError::InvalidTransparentComponent("No circuit field".into()))?;
+            let pin_mapping = def.pin_mapping.as_ref().ok_or(DefinitionError::InvalidTransparentComponent("No pinMapping field".into()))?;
+            let inner_prefix = format!("{}/", path);
+            let inner_components: Vec<(Id, i32)> = inner.components.iter().map(|x| (x.id, x.def_id)).collect();
+            let inner_connections: Vec<(Connector, &[Connector])> = inner.connections.iter().map(|x| (x.from, x.to.as_slice())).collect();
+            self.scope(&inner_prefix, &inner_components, &inner_connections, depth + 1)?;
+
+            let mut edges = Vec::new();
+            for (pin, to) in pin_mapping.input.iter().enumerate() {
+                for to in to.iter() {
+                    let head = self.endpoint(&inner_prefix, &inner_components, *to)?;
+                    edges.push((pin_node(&path, pin as Id), None, head.0, head.1));
+                }
+            }
+            for (pin, from) in pin_mapping.output.iter().enumerate() {
+                for from in from.iter() {
+                    let tail = self.endpoint(&inner_prefix, &inner_components, *from)?;
+                    edges.push((tail.0, tail.1, pin_node(&path, (def.pins.input.len() + pin) as Id), None));
+                }
+            }
+            // Connections through the definition's pins
+            self.write_edges(&edges, depth + 1);
+
+            writeln!(self.out, "{}}}", indent).unwrap();
+            self.stack.pop();
+        }
+
+        let mut edges = Vec::new();
+        for &(from, to) in connections {
+            for to in to.iter() {
+                edges.push(self.edge(prefix, components, from, *to)?);
+            }
+        }
+        self.write_edges(&edges, depth);
+
+        Ok(())
+    }
+
+    fn write_edges(&mut self, edges: &[Edge], depth: usize) {
+        let indent = "    ".repeat(depth);
+        for (tail, tail_label, head, head_label) in edges {
+            let mut attributes = Vec::new();
+            if let Some(label) = tail_label {
+                attributes.push(format!("taillabel=\"{}\"", escape(label)));
+            }
+            if let Some(label) = head_label {
+                attributes.push(format!("headlabel=\"{}\"", escape(label)));
+            }
+
+            if attributes.is_empty() {
+                writeln!(self.out, "{}\"{}\" -> \"{}\";", indent, tail, head).unwrap();
+            } else {
+                writeln!(self.out, "{}\"{}\" -> \"{}\" [{}];", indent, tail, head, attributes.join(", ")).unwrap();
+            }
+        }
+    }
+
+    fn edge(&self, prefix: &str, components: &[(Id, i32)], from: Connector, to: Connector) -> Result<Edge, ExportError> {
+        let tail = self.endpoint(prefix, components, from)?;
+        let head = self.endpoint(prefix, components, to)?;
+        Ok((tail.0, tail.1, head.0, head.1))
+    }
+
+    /// Returns the node of a connector, along with the pin label if the node is not a pin itself.
+    fn endpoint(&self, prefix: &str, components: &[(Id, i32)], connector: Connector) -> Result<(String, Option<String>), ExportError> {
+        let def_id = components.iter()
+            .find(|x| x.0 == connector.component)
+            .map(|x| x.1)
+            .ok_or(DefinitionError::InvalidConnector(connector))?;
+        let def: &ComponentDefinition = self.registry.get_definition(def_id)?;
+        let path = format!("{}{}", prefix, connector.component);
+
+        if def.kind == ComponentKind::Transparent {
+            Ok((pin_node(&path, connector.pin), None))
+        } else {
+            Ok((path, Some(pin_name(&def.pins, connector.pin))))
+        }
+    }
+}
+
+fn header(out: &mut String, graph_name: &str) {
+    writeln!(out, "digraph \"{}\" {{", escape(graph_name)).unwrap();
+    writeln!(out, "    rankdir=LR;").unwrap();
+    writeln!(out, "    node [shape=box];").unwrap();
+}
+
+fn pin_node(path: &str, pin: Id) -> String {
+    format!("{}:{}", path, pin)
+}
+
+/// Returns the name of the pin, falling back to its index for pins without names.
+fn pin_name(pins: &Pins, pin: Id) -> String {
+    pins.input.iter()
+        .chain(pins.output.iter())
+        .nth(pin as usize)
+        .cloned()
+        .unwrap_or_else(|| pin.to_string())
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_registry() -> Registry {
+        let mut registry = Registry::default();
+        for def in [
+            include_str!("../../tests/assets/and_gate_definition.json"),
+            include_str!("../../tests/assets/not_gate_definition.json"),
+            include_str!("../../tests/assets/ab_inverted_definition.json"),
+        ] {
+            let parsed: ComponentDefinition = serde_json::from_str(def).unwrap();
+            registry.insert(parsed);
+        }
+
+        registry
+    }
+
+    #[test]
+    fn hierarchical_graph() {
+        let registry = load_registry();
+        let circuit_def: CircuitDefinition = serde_json::from_str(include_str!("../../tests/assets/ab_inverted_on_not_circuit.json")).unwrap();
+        let dot = circuit_definition_to_dot(&registry, &circuit_def).unwrap();
+
+        let expected = r#"digraph "^(A * ^B)" {
+    rankdir=LR;
+    node [shape=box];
+    subgraph "cluster_0" {
+        label="0: AB Inverted";
+        "0:0" [label="A", shape=plaintext];
+        "0:1" [label="B", shape=plaintext];
+        "0:2" [label="Y", shape=plaintext];
+        subgraph "cluster_0/0" {
+            label="0: NotGate";
+            "0/0:0" [label="A", shape=plaintext];
+            "0/0:1" [label="Y", shape=plaintext];
+            "0/0/0" [label="0: NAND Gate"];
+            "0/0:0" -> "0/0/0" [headlabel="A"];
+            "0/0:0" -> "0/0/0" [headlabel="B"];
+            "0/0/0" -> "0/0:1" [taillabel="Y"];
+        }
+        subgraph "cluster_0/1" {
+            label="1: AndGate";
+            "0/1:0" [label="A", shape=plaintext];
+            "0/1:1" [label="B", shape=plaintext];
+            "0/1:2" [label="Y", shape=plaintext];
+            "0/1/0" [label="0: NAND Gate"];
+            "0/1/1" [label="1: NAND Gate"];
+            "0/1/0" -> "0/1/1" [taillabel="Y", headlabel="A"];
+            "0/1/0" -> "0/1/1" [taillabel="Y", headlabel="B"];
+            "0/1:0" -> "0/1/0" [headlabel="A"];
+            "0/1:1" -> "0/1/0" [headlabel="B"];
+            "0/1/1" -> "0/1:2" [taillabel="Y"];
+        }
+        "0/0:1" -> "0/1:1";
+        "0:0" -> "0/0:0";
+        "0:1" -> "0/1:0";
+        "0/1:2" -> "0:2";
+    }
+    subgraph "cluster_1" {
+        label="1: NotGate";
+        "1:0" [label="A", shape=plaintext];
+        "1:1" [label="Y", shape=plaintext];
+        "1/0" [label="0: NAND Gate"];
+        "1:0" -> "1/0" [headlabel="A"];
+        "1:0" -> "1/0" [headlabel="B"];
+        "1/0" -> "1:1" [taillabel="Y"];
+    }
+    "0:2" -> "1:0";
+}
+"#;
+        assert_eq!(dot, expected);
+    }
+
+    #[test]
+    fn flattened_graph() {
+        let registry = load_registry();
+        let circuit_def: CircuitDefinition = serde_json::from_str(include_str!("../../tests/assets/ab_inverted_on_not_circuit.json")).unwrap();
+        let circuit = Circuit::from_definition(&registry, circuit_def).unwrap();
+
+        let expected = r#"digraph "flat" {
+    rankdir=LR;
+    node [shape=box];
+    "0/0/0" [label="0/0/0: NAND Gate"];
+    "0/1/0" [label="0/1/0: NAND Gate"];
+    "0/1/1" [label="0/1/1: NAND Gate"];
+    "1/0" [label="1/0: NAND Gate"];
+    "0/0/0" -> "0/1/0" [taillabel="Y", headlabel="B"];
+    "0/1/0" -> "0/1/1" [taillabel="Y", headlabel="A"];
+    "0/1/0" -> "0/1/1" [taillabel="Y", headlabel="B"];
+    "0/1/1" -> "1/0" [taillabel="Y", headlabel="A"];
+    "0/1/1" -> "1/0" [taillabel="Y", headlabel="B"];
+}
+"#;
+        assert_eq!(circuit_to_dot(&circuit, "flat"), expected);
+    }
+}
@@ -0,0 +1,62 @@
+mod verilog;
+mod dot;
+mod vcd;
+pub use verilog::{circuit_to_verilog, definition_to_verilog};
+pub use dot::{circuit_to_dot, circuit_definition_to_dot};
+pub use vcd::simulation_to_vcd;
+pub(crate) use verilog::{port_names, sanitize};
+
+use crate::Component;
+use crate::circuit::{Circuit, DefinitionError, Id};
+use crate::circuit::registry::{self, RegistryError};
+use crate::component::{Button, Clock, Ground, Led, Nand, Source, Switch, Tristate};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("Component {0} of type '{1}' cannot be exported.")]
+    UnsupportedComponent(String, String),
+
+    #[error("Definition {0} is not a transparent component definition.")]
+    NotTransparent(i32),
+
+    #[error("Encountered a definition error.")]
+    DefinitionError(#[from] DefinitionError),
+}
+
+impl From<RegistryError> for ExportError {
+    fn from(e: RegistryError) -> Self {
+        Self::DefinitionError(e.into())
+    }
+}
+
+/// Returns the prebuilt definition ID of a concrete component.
+pub(crate) fn builtin_id(component: &dyn Component) -> Option<i32> {
+    let any = component.as_any();
+    if any.is::<Nand>() {
+        Some(registry::NAND_ID)
+    } else if any.is::<Tristate>() {
+        Some(registry::TRISTATE_ID)
+    } else if any.is::<Clock>() {
+        Some(registry::CLOCK_ID)
+    } else if any.is::<Ground>() {
+        Some(registry::GROUND_ID)
+    } else if any.is::<Source>() {
+        Some(registry::SOURCE_ID)
+    } else if any.is::<Switch>() {
+        Some(registry::SWITCH_ID)
+    } else if any.is::<Led>() {
+        Some(registry::LED_ID)
+    } else if any.is::<Button>() {
+        Some(registry::BUTTON_ID)
+    } else {
+        None
+    }
+}
+
+/// Returns a label for the flattened component, preferring its hierarchical path.
+pub(crate) fn component_label(circuit: &Circuit, id: Id) -> String {
+    match circuit.hierarchy.path_of(id) {
+        Some(path) => path.0.iter().map(|x| x.to_string()).collect::<Vec<_>>().join("_"),
+        None => id.to_string(),
+    }
+}
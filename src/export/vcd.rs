@@ -0,0 +1,148 @@
+use std::fmt::Write;
+
+use crate::Simulation;
+use crate::circuit::{Circuit, Connector};
+use crate::circuit::registry::{BUTTON_ID, CLOCK_ID, SWITCH_ID};
+use super::{builtin_id, component_label};
+
+/// Simulates the circuit for the number of ticks, recording the value on every net as a Value
+/// Change Dump.
+///
+/// Each tick is written as one time unit. The simulation should already be initialized, and nets
+/// which have not been driven yet read as 0, same as in the simulation. Inspection is enabled if
+/// it was not already, so nets driven before then also read as 0 until they change.
+pub fn simulation_to_vcd(sim: &mut Simulation, num_ticks: usize) -> String {
+    sim.enable_inspection();
+    let nets = nets(&sim.circuit);
+    let value = |sim: &Simulation, net: &Connector| sim.nets.as_ref().and_then(|x| x.get(net)).copied().unwrap_or_default();
+
+    let mut out = String::new();
+    out.push_str("$timescale 1ns $end\n");
+    out.push_str("$scope module circuit $end\n");
+    for (i, (_, name)) in nets.iter().enumerate() {
+        writeln!(out, "$var wire 1 {} {} $end", identifier(i), name).unwrap();
+    }
+    out.push_str("$upscope $end\n");
+    out.push_str("$enddefinitions $end\n");
+
+    writeln!(out, "#{}", sim.elapsed).unwrap();
+    out.push_str("$dumpvars\n");
+    let mut last: Vec<bool> = nets.iter().map(|(net, _)| value(sim, net)).collect();
+    for (i, &value) in last.iter().enumerate() {
+        writeln!(out, "{}{}", value as u8, identifier(i)).unwrap();
+    }
+    out.push_str("$end\n");
+
+    for _ in 0..num_ticks {
+        sim.tick();
+
+        let mut changes = String::new();
+        for (i, (net, _)) in nets.iter().enumerate() {
+            let value = value(sim, net);
+            if value != last[i] {
+                last[i] = value;
+                writeln!(changes, "{}{}", value as u8, identifier(i)).unwrap();
+            }
+        }
+        if !changes.is_empty() {
+            writeln!(out, "#{}", sim.elapsed).unwrap();
+            out.push_str(&changes);
+        }
+    }
+
+    out
+}
+
+/// Returns the output connectors of the concrete components in hierarchical order, named the same
+/// way as the nets of the exported Verilog.
+fn nets(circuit: &Circuit) -> Vec<(Connector, String)> {
+    let mut nets: Vec<Connector> = circuit.connections.keys()
+        .filter(|x| builtin_id(circuit.components[&x.component].as_ref()).is_some())
+        .copied()
+        .collect();
+    nets.sort_by_key(|x| (circuit.hierarchy.path_of(x.component).map(|x| x.0.clone()).unwrap_or_else(|| vec![x.component]), x.pin));
+
+    nets.into_iter()
+        .map(|net| {
+            let label = component_label(circuit, net.component);
+            let name = match builtin_id(circuit.components[&net.component].as_ref()) {
+                Some(SWITCH_ID | BUTTON_ID) => format!("in_{}", label),
+                Some(CLOCK_ID) => format!("clk_{}", label),
+                _ => format!("n_{}_{}", label, net.pin),
+            };
+            (net, name)
+        })
+        .collect()
+}
+
+/// Encodes the index of a variable in the printable ASCII characters.
+fn identifier(mut index: usize) -> String {
+    let mut identifier = String::new();
+    loop {
+        identifier.push((b'!' + (index % 94) as u8) as char);
+        index /= 94;
+        if index == 0 {
+            return identifier;
+        }
+        index -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{CircuitDefinition, Registry};
+    use crate::sim::Settings;
+
+    #[test]
+    fn value_changes() {
+        // A constant 1 inverted twice by NAND gates with both inputs tied together, each taking
+        // two ticks. The first inverter only ever outputs 0, so it never changes
+        let circuit_def: CircuitDefinition = serde_json::from_value(serde_json::json!({
+            "id": 0,
+            "name": "Inverter",
+            "description": "",
+            "components": [
+                { "definitionId": -5, "id": 0 },
+                { "definitionId": -1, "id": 1 },
+                { "definitionId": -1, "id": 2 },
+            ],
+            "connections": [
+                { "from": { "componentId": 0, "pin": 0 }, "to": [{ "componentId": 1, "pin": 0 }, { "componentId": 1, "pin": 1 }] },
+                { "from": { "componentId": 1, "pin": 2 }, "to": [{ "componentId": 2, "pin": 0 }, { "componentId": 2, "pin": 1 }] },
+            ],
+        })).unwrap();
+        let circuit = Circuit::from_definition(&Registry::default(), circuit_def).unwrap();
+        let mut sim = Simulation::with_circuit(Settings::default(), circuit);
+        sim.init();
+
+        let expected = "\
+$timescale 1ns $end
+$scope module circuit $end
+$var wire 1 ! n_0_0 $end
+$var wire 1 \" n_1_2 $end
+$var wire 1 # n_2_2 $end
+$upscope $end
+$enddefinitions $end
+#0
+$dumpvars
+0!
+0\"
+0#
+$end
+#1
+1!
+#5
+1#
+";
+        assert_eq!(simulation_to_vcd(&mut sim, 8), expected);
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(identifier(0), "!");
+        assert_eq!(identifier(93), "~");
+        assert_eq!(identifier(94), "!!");
+        assert_eq!(identifier(95), "\"!");
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use rassert_rs::rassert;
+
+use crate::Circuit;
+use crate::circuit::{Connector, DefinitionError, Id, Registry};
+use crate::circuit::registry::{NAND_ID, TRISTATE_ID, CLOCK_ID, GROUND_ID, SOURCE_ID, SWITCH_ID, LED_ID, BUTTON_ID};
+use crate::component::{ComponentDefinition, Generic, Wiring};
+use crate::component::definition::{ComponentKind, Pins};
+use super::{builtin_id, component_label, ExportError};
+use ExportError::*;
+
+/// Reserved words of IEEE 1364-2005, which get a trailing underscore when used as names.
+const KEYWORDS: &[&str] = &[
+    "always", "and", "assign", "automatic", "begin", "buf", "bufif0", "bufif1", "case", "casex",
+    "casez", "cell", "cmos", "config", "deassign", "default", "defparam", "design", "disable",
+    "edge", "else", "end", "endcase", "endconfig", "endfunction", "endgenerate", "endmodule",
+    "endprimitive", "endspecify", "endtable", "endtask", "event", "for", "force", "forever", "fork",
+    "function", "generate", "genvar", "highz0", "highz1", "if", "ifnone", "incdir", "include",
+    "initial", "inout", "input", "instance", "integer", "join", "large", "liblist", "library",
+    "localparam", "macromodule", "medium", "module", "nand", "negedge", "nmos", "nor",
+    "noshowcancelled", "not", "notif0", "notif1", "or", "output", "parameter", "pmos", "posedge",
+    "primitive", "pull0", "pull1", "pulldown", "pullup", "pulsestyle_ondetect",
+    "pulsestyle_onevent", "rcmos", "real", "realtime", "reg", "release", "repeat", "rnmos", "rpmos",
+    "rtran", "rtranif0", "rtranif1", "scalared", "showcancelled", "signed", "small", "specify",
+    "specparam", "strong0", "strong1", "supply0", "supply1", "table", "task", "time", "tran",
+    "tranif0", "tranif1", "tri", "tri0", "tri1", "triand", "trior", "trireg", "unsigned", "use",
+    "uwire", "vectored", "wait", "wand", "weak0", "weak1", "while", "wire", "wor", "xnor", "xor",
+];
+
+/// A structural Verilog module under construction.
+#[derive(Debug, Default)]
+struct Module {
+    name: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    wires: BTreeSet<String>,
+    statements: Vec<String>,
+}
+
+impl Module {
+    /// Returns the expression driving a sink with the given drivers.
+    ///
+    /// Sinks with multiple drivers (e.g. tristate buses) get their own wire.
+    fn sink(&mut self, bus_name: String, drivers: &[String]) -> String {
+        match drivers {
+            [] => "1'b0".into(),
+            [driver] => driver.clone(),
+            drivers => {
+                for driver in drivers {
+                    self.statements.push(format!("assign {} = {};", bus_name, driver));
+                }
+                self.wires.insert(bus_name.clone());
+                bus_name
+            },
+        }
+    }
+
+    fn write(&self, out: &mut String) {
+        let ports: Vec<&str> = self.inputs.iter().chain(self.outputs.iter()).map(|x| x.as_str()).collect();
+        writeln!(out, "module {} ({});", self.name, ports.join(", ")).unwrap();
+        self.inputs.iter().for_each(|x| writeln!(out, "    input {};", x).unwrap());
+        self.outputs.iter().for_each(|x| writeln!(out, "    output {};", x).unwrap());
+        self.wires.iter().for_each(|x| writeln!(out, "    wire {};", x).unwrap());
+        if !self.statements.is_empty() {
+            out.push('\n');
+        }
+        self.statements.iter().for_each(|x| writeln!(out, "    {}", x).unwrap());
+        writeln!(out, "endmodule").unwrap();
+    }
+}
+
+/// Exports a flattened circuit as a single structural Verilog module.
+///
+/// Switches, buttons and clocks become input ports and LEDs become output ports. Inputs which are
+/// not driven by anything are tied to 0, same as in the simulation.
+pub fn circuit_to_verilog(circuit: &Circuit, module_name: &str) -> Result<String, ExportError> {
+    let mut module = Module { name: sanitize(module_name), ..Default::default() };
+
+    // Name every net after the concrete connector driving it
+    let net_name = |connector: Connector| {
+        let label = component_label(circuit, connector.component);
+        match builtin_id(circuit.components[&connector.component].as_ref()) {
+            Some(SWITCH_ID | BUTTON_ID) => format!("in_{}", label),
+            Some(CLOCK_ID) => format!("clk_{}", label),
+            _ => format!("n_{}_{}", label, connector.pin),
+        }
+    };
+
+    let mut drivers: HashMap<Connector, Vec<Connector>> = HashMap::new();
+    for (from, to) in circuit.connections.iter() {
+        to.iter().for_each(|x| drivers.entry(*x).or_default().push(*from));
+    }
+
+    // Emit components in hierarchical order
+    let mut ids: Vec<Id> = circuit.components.keys().copied().collect();
+    ids.sort_by_key(|&id| circuit.hierarchy.path_of(id).map(|x| x.0.clone()).unwrap_or_else(|| vec![id]));
+    for id in ids {
+        let component = circuit.components[&id].as_ref();
+        let label = component_label(circuit, id);
+        let sink = |module: &mut Module, pin: Id| {
+            let mut connectors = drivers.get(&Connector::new(id, pin)).cloned().unwrap_or_default();
+            connectors.sort_by_key(|x| (x.component, x.pin));
+            let names: Vec<String> = connectors.into_iter().map(net_name).collect();
+            module.sink(format!("bus_{}_{}", label, pin), &names)
+        };
+
+        match builtin_id(component) {
+            Some(kind @ (NAND_ID | TRISTATE_ID)) => {
+                let output = net_name(Connector::new(id, 2));
+                let a = sink(&mut module, 0);
+                let b = sink(&mut module, 1);
+                let primitive = if kind == NAND_ID { "nand" } else { "bufif1" };
+                module.wires.insert(output.clone());
+                module.statements.push(format!("{} g_{} ({}, {}, {});", primitive, label, output, a, b));
+            },
+            Some(kind @ (GROUND_ID | SOURCE_ID)) => {
+                let output = net_name(Connector::new(id, 0));
+                let value = if kind == GROUND_ID { "1'b0" } else { "1'b1" };
+                module.wires.insert(output.clone());
+                module.statements.push(format!("assign {} = {};", output, value));
+            },
+            Some(SWITCH_ID | BUTTON_ID | CLOCK_ID) => module.inputs.push(net_name(Connector::new(id, 0))),
+            Some(LED_ID) => {
+                let output = format!("out_{}", label);
+                let a = sink(&mut module, 0);
+                module.outputs.push(output.clone());
+                module.statements.push(format!("assign {} = {};", output, a));
+            },
+            // Transparent placeholders and the wiring do not exist in hardware
+            _ if component.as_any().is::<Generic>() || component.as_any().is::<Wiring>() => {},
+            _ => return Err(UnsupportedComponent(label, "unknown".into())),
+        }
+    }
+
+    let mut out = String::new();
+    module.write(&mut out);
+    Ok(out)
+}
+
+/// Exports a transparent definition and all transparent definitions it uses as structural
+/// Verilog modules, declaring dependencies first.
+///
+/// Pin names become port names, builtin components become gate primitives.
+pub fn definition_to_verilog(registry: &Registry, def_id: i32) -> Result<String, ExportError> {
+    let root = registry.get_definition(def_id)?;
+    rassert!(root.kind == ComponentKind::Transparent, NotTransparent(def_id));
+    if let Some(cycle) = registry.find_cycle(root) {
+        return Err(DefinitionError::RecursiveDefinition(cycle).into());
+    }
+
+    let mut order = Vec::new();
+    collect_transparent(registry, root, &mut order, &mut HashSet::new())?;
+
+    // Module names have to be unique even if definition names are not
+    let mut names = HashMap::new();
+    let mut taken = HashSet::new();
+    for def in order.iter() {
+        let mut name = sanitize(&def.name);
+        if !taken.insert(name.clone()) {
+            name = format!("{}_{}", name, def.id);
+            taken.insert(name.clone());
+        }
+        names.insert(def.id, name);
+    }
+
+    let mut out = String::new();
+    for def in order {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        definition_module(registry, def, &names)?.write(&mut out);
+    }
+
+    Ok(out)
+}
+
+/// Collects the transparent definitions in dependency order.
+fn collect_transparent<'a>(registry: &'a Registry, def: &'a ComponentDefinition, order: &mut Vec<&'a ComponentDefinition>, visited: &mut HashSet<i32>) -> Result<(), ExportError> {
+    if !visited.insert(def.id) {
+        return Ok(());
+    }
+
+    for dependency in def.dependencies() {
+        let dependency = registry.get_definition(dependency)?;
+        if dependency.kind == ComponentKind::Transparent {
+            collect_transparent(registry, dependency, order, visited)?;
+        }
+    }
+    order.push(def);
+
+    Ok(())
+}
+
+fn definition_module(registry: &Registry, def: &ComponentDefinition, names: &HashMap<i32, String>) -> Result<Module, ExportError> {
+    let circuit = def.circuit.as_ref().ok_or(NotTransparent(def.id))?;
+    let pin_mapping = def.pin_mapping.as_ref().ok_or(NotTransparent(def.id))?;
+    let ports = port_names(&def.pins);
+    let num_inputs = def.pins.input.len();
+    let mut module = Module {
+        name: names[&def.id].clone(),
+        inputs: ports[..num_inputs].to_vec(),
+        outputs: ports[num_inputs..].to_vec(),
+        ..Default::default()
+    };
+
+    // Collect the drivers of every inner input pin
+    let mut drivers: HashMap<Connector, Vec<String>> = HashMap::new();
+    for (port, connectors) in ports.iter().zip(pin_mapping.input.iter()) {
+        connectors.iter().for_each(|x| drivers.entry(*x).or_default().push(port.clone()));
+    }
+    for connection in circuit.connections.iter() {
+        let net = inner_net(connection.from);
+        module.wires.insert(net.clone());
+        connection.to.iter().for_each(|x| drivers.entry(*x).or_default().push(net.clone()));
+    }
+
+    let mut components = circuit.components.clone();
+    components.sort_by_key(|x| x.id);
+    for component in components {
+        let id = component.id;
+        let component_def = registry.get_definition(component.def_id)?;
+        let sink = |module: &mut Module, pin: Id| {
+            let names = drivers.get(&Connector::new(id, pin)).cloned().unwrap_or_default();
+            module.sink(format!("bus${}_{}", id, pin), &names)
+        };
+
+        match (component_def.kind, component_def.id) {
+            (ComponentKind::Builtin, kind @ (NAND_ID | TRISTATE_ID)) => {
+                let output = inner_net(Connector::new(id, 2));
+                let a = sink(&mut module, 0);
+                let b = sink(&mut module, 1);
+                let primitive = if kind == NAND_ID { "nand" } else { "bufif1" };
+                module.wires.insert(output.clone());
+                module.statements.push(format!("{} g${} ({}, {}, {});", primitive, id, output, a, b));
+            },
+            (ComponentKind::Builtin, kind @ (GROUND_ID | SOURCE_ID)) => {
+                let output = inner_net(Connector::new(id, 0));
+                let value = if kind == GROUND_ID { "1'b0" } else { "1'b1" };
+                module.wires.insert(output.clone());
+                module.statements.push(format!("assign {} = {};", output, value));
+            },
+            (ComponentKind::Transparent, _) => {
+                let inner_ports = port_names(&component_def.pins);
+                let inner_inputs = component_def.pins.input.len();
+                let mut bindings = Vec::with_capacity(inner_ports.len());
+                for (pin, port) in inner_ports.iter().enumerate() {
+                    let pin = pin as Id;
+                    let net = if (pin as usize) < inner_inputs {
+                        sink(&mut module, pin)
+                    } else {
+                        let net = inner_net(Connector::new(id, pin));
+                        module.wires.insert(net.clone());
+                        net
+                    };
+                    bindings.push(format!(".{}({})", port, net));
+                }
+                module.statements.push(format!("{} u${} ({});", names[&component_def.id], id, bindings.join(", ")));
+            },
+            _ => return Err(UnsupportedComponent(format!("{} inside '{}'", id, def.name), component_def.name.clone())),
+        }
+    }
+
+    // Drive the output ports
+    for (port, connectors) in ports[num_inputs..].iter().zip(pin_mapping.output.iter()) {
+        if connectors.is_empty() {
+            module.statements.push(format!("assign {} = 1'b0;", port));
+        }
+        for &connector in connectors {
+            module.statements.push(format!("assign {} = {};", port, inner_net(connector)));
+        }
+    }
+
+    Ok(module)
+}
+
+/// Names the net driven by the connector. Generated names contain a `$`, which sanitized port
+/// names never do, so that they cannot collide.
+fn inner_net(connector: Connector) -> String {
+    format!("n${}_{}", connector.component, connector.pin)
+}
+
+/// Returns unique port names for the pins, inputs first.
+pub(crate) fn port_names(pins: &Pins) -> Vec<String> {
+    let mut taken = HashSet::new();
+    pins.input.iter().chain(pins.output.iter())
+        .enumerate()
+        .map(|(i, name)| {
+            let mut name = sanitize(name);
+            if !taken.insert(name.clone()) {
+                name = format!("{}_{}", name, i);
+                taken.insert(name.clone());
+            }
+            name
+        })
+        .collect()
+}
+
+/// Turns a name into a valid Verilog identifier.
+pub(crate) fn sanitize(name: &str) -> String {
+    let mut sanitized: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    if sanitized.is_empty() || sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+    if KEYWORDS.contains(&sanitized.as_str()) {
+        sanitized.push('_');
+    }
+
+    sanitized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{CircuitDefinition, Connection};
+    use crate::component::definition::Component;
+
+    fn load_registry() -> Registry {
+        let mut registry = Registry::default();
+        for def in [
+            include_str!("../../tests/assets/and_gate_definition.json"),
+            include_str!("../../tests/assets/not_gate_definition.json"),
+            include_str!("../../tests/assets/ab_inverted_definition.json"),
+        ] {
+            let parsed: ComponentDefinition = serde_json::from_str(def).unwrap();
+            registry.insert(parsed);
+        }
+
+        registry
+    }
+
+    #[test]
+    fn hierarchical_modules() {
+        let registry = load_registry();
+        let verilog = definition_to_verilog(&registry, 3).unwrap();
+
+        let expected = "\
+module NotGate (A, Y);
+    input A;
+    output Y;
+    wire n$0_2;
+
+    nand g$0 (n$0_2, A, A);
+    assign Y = n$0_2;
+endmodule
+
+module AndGate (A, B, Y);
+    input A;
+    input B;
+    output Y;
+    wire n$0_2;
+    wire n$1_2;
+
+    nand g$0 (n$0_2, A, B);
+    nand g$1 (n$1_2, n$0_2, n$0_2);
+    assign Y = n$1_2;
+endmodule
+
+module AB_Inverted (A, B, Y);
+    input A;
+    input B;
+    output Y;
+    wire n$0_1;
+    wire n$1_2;
+
+    NotGate u$0 (.A(A), .Y(n$0_1));
+    AndGate u$1 (.A(B), .B(n$0_1), .Y(n$1_2));
+    assign Y = n$1_2;
+endmodule
+";
+        assert_eq!(verilog, expected);
+        assert!(matches!(definition_to_verilog(&registry, NAND_ID), Err(NotTransparent(NAND_ID))));
+    }
+
+    #[test]
+    fn flattened_module() {
+        let registry = load_registry();
+        let circuit_def = CircuitDefinition {
+            name: "not circuit".into(),
+            components: vec![
+                Component { def_id: 2, id: 0 },
+                Component { def_id: SWITCH_ID, id: 1 },
+                Component { def_id: LED_ID, id: 2 },
+            ],
+            connections: vec![
+                Connection { from: Connector::new(1, 0), to: vec![Connector::new(0, 0)] },
+                Connection { from: Connector::new(0, 1), to: vec![Connector::new(2, 0)] },
+            ],
+            ..Default::default()
+        };
+        let circuit = Circuit::from_definition(&registry, circuit_def).unwrap();
+        let verilog = circuit_to_verilog(&circuit, "not circuit").unwrap();
+
+        let expected = "\
+module not_circuit (in_1, out_2);
+    input in_1;
+    output out_2;
+    wire n_0_0_2;
+
+    nand g_0_0 (n_0_0_2, in_1, in_1);
+    assign out_2 = n_0_0_2;
+endmodule
+";
+        assert_eq!(verilog, expected);
+    }
+}
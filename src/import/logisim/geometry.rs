@@ -0,0 +1,249 @@
+/// A point on the Logisim canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize)]
+pub struct Location {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Location {
+    /// Parses a location in the `(x,y)` or `x,y` format.
+    pub(crate) fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().trim_start_matches('(').trim_end_matches(')');
+        let (x, y) = s.split_once(',')?;
+        Some(Self { x: x.trim().parse().ok()?, y: y.trim().parse().ok()? })
+    }
+
+    pub(crate) fn translate(self, (dx, dy): (i32, i32)) -> Self {
+        Self { x: self.x + dx, y: self.y + dy }
+    }
+
+    pub(crate) fn offset_from(self, other: Location) -> (i32, i32) {
+        (self.x - other.x, self.y - other.y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Direction {
+    East,
+    North,
+    West,
+    South,
+}
+
+impl Direction {
+    /// Parses a Logisim facing attribute, defaulting to east.
+    pub fn parse(s: Option<&str>) -> Self {
+        match s {
+            Some("north") => Self::North,
+            Some("west") => Self::West,
+            Some("south") => Self::South,
+            _ => Self::East,
+        }
+    }
+
+    pub fn reverse(self) -> Self {
+        self.rotate_to(Self::West)
+    }
+
+    /// Counter-clockwise quarter turns from east.
+    fn quarter_turns(self) -> u8 {
+        match self {
+            Self::East => 0,
+            Self::North => 1,
+            Self::West => 2,
+            Self::South => 3,
+        }
+    }
+
+    fn from_quarter_turns(turns: u8) -> Self {
+        match turns % 4 {
+            0 => Self::East,
+            1 => Self::North,
+            2 => Self::West,
+            _ => Self::South,
+        }
+    }
+
+    /// Returns the direction rotated by as many quarter turns as `by` is from east.
+    fn rotate_to(self, by: Self) -> Self {
+        Self::from_quarter_turns(self.quarter_turns() + by.quarter_turns())
+    }
+
+    /// Rotates an offset of a component facing east as if the component faced this direction.
+    pub fn rotate(self, offset: (i32, i32)) -> (i32, i32) {
+        self.rotate_from(Self::East, offset)
+    }
+
+    /// Rotates an offset of a component facing `from` as if the component faced this direction.
+    pub fn rotate_from(self, from: Self, offset: (i32, i32)) -> (i32, i32) {
+        let turns = (4 + self.quarter_turns() - from.quarter_turns()) % 4;
+        (0..turns).fold(offset, |(x, y), _| (y, -x))
+    }
+}
+
+/// Describes the shape of a multi-input gate.
+pub(crate) struct GateShape {
+    pub facing: Direction,
+    pub size: i32,
+    pub inputs: usize,
+    pub bonus_width: i32,
+    pub negate_output: bool,
+}
+
+impl GateShape {
+    /// Returns the offset of the input from the gate's output, mirroring Logisim's layout.
+    pub fn input_offset(&self, index: usize, negated: bool) -> (i32, i32) {
+        let inputs = self.inputs as i32;
+        let index = index as i32;
+        let (skip_start, skip_dist, skip_lower_even) = if inputs <= 3 {
+            if self.size < 40 {
+                (-5, 10, 10)
+            } else if self.size < 60 || inputs <= 2 {
+                (-10, 20, 20)
+            } else {
+                (-15, 30, 30)
+            }
+        } else if inputs == 4 && self.size >= 60 {
+            (-5, 20, 0)
+        } else {
+            (-5, 10, 10)
+        };
+
+        let dy = if inputs % 2 == 1 {
+            skip_start * (inputs - 1) + skip_dist * index
+        } else {
+            skip_start * inputs + skip_dist * index + if index >= inputs / 2 { skip_lower_even } else { 0 }
+        };
+        let dx = self.size + self.bonus_width + if self.negate_output { 10 } else { 0 } + if negated { 10 } else { 0 };
+
+        match self.facing {
+            Direction::North => (dy, dx),
+            Direction::South => (dy, -dx),
+            Direction::West => (dx, dy),
+            Direction::East => (-dx, dy),
+        }
+    }
+}
+
+/// A pin of a circuit, as seen by the instances of the circuit.
+#[derive(Debug, Clone)]
+pub(crate) struct PinShape {
+    pub location: Location,
+    pub facing: Direction,
+    pub output: bool,
+}
+
+/// The appearance of a circuit when it is used as a subcircuit.
+#[derive(Debug, Clone)]
+pub(crate) enum Appearance {
+    /// The box of Logisim 2.x, with pins on the edge opposite to their facing.
+    Classic,
+    /// The default box of Logisim Evolution, with inputs on the west and outputs on the east edge.
+    ///
+    /// The box width depends on the rendered label widths, so it has to be supplied.
+    Evolution,
+    /// A custom appearance, mapping pin locations inside the circuit to port locations.
+    Custom { anchor: Location, facing: Direction, ports: Vec<(Location, Location)> },
+}
+
+impl Appearance {
+    /// Computes the port offsets of an instance from its location, in the order of `pins`.
+    ///
+    /// The pins are expected to be sorted by their location. `width` is only used by the
+    /// Evolution appearance.
+    pub fn port_offsets(&self, pins: &[PinShape], facing: Direction, width: i32) -> Vec<Option<(i32, i32)>> {
+        match self {
+            Self::Classic => classic_offsets(pins, facing).into_iter().map(Some).collect(),
+            Self::Evolution => {
+                let num_outputs = pins.iter().filter(|x| x.output).count();
+                let anchor = (if num_outputs > 0 { width } else { 0 }, 10);
+
+                let (mut west, mut east) = (0, 0);
+                pins.iter()
+                    .map(|pin| {
+                        let row = if pin.output { &mut east } else { &mut west };
+                        let port = (if pin.output { width } else { 0 }, 10 + 10 * *row);
+                        *row += 1;
+                        Some(facing.rotate((port.0 - anchor.0, port.1 - anchor.1)))
+                    })
+                    .collect()
+            },
+            Self::Custom { anchor, facing: anchor_facing, ports } => pins.iter()
+                .map(|pin| {
+                    ports.iter()
+                        .find(|x| x.0 == pin.location)
+                        .map(|x| facing.rotate_from(*anchor_facing, x.1.offset_from(*anchor)))
+                })
+                .collect(),
+        }
+    }
+}
+
+fn classic_offsets(pins: &[PinShape], facing: Direction) -> Vec<(i32, i32)> {
+    fn compute_offset(num_facing: i32, num_opposite: i32, max_others: i32) -> i32 {
+        let max_this = num_facing.max(num_opposite);
+        let max_offset = match max_this {
+            0 | 1 => if max_others == 0 { 15 } else { 10 },
+            2 => 10,
+            _ => if max_others == 0 { 5 } else { 10 },
+        };
+        max_offset + 10 * ((max_this - num_facing) / 2)
+    }
+
+    fn compute_dimension(max_this: i32, max_others: i32) -> i32 {
+        if max_this < 3 {
+            30
+        } else if max_others == 0 {
+            10 * max_this
+        } else {
+            10 * max_this + 10
+        }
+    }
+
+    let edge = |pin: &PinShape| pin.facing.reverse();
+    let count = |direction| pins.iter().filter(|x| edge(x) == direction).count() as i32;
+    let (north, south, east, west) = (count(Direction::North), count(Direction::South), count(Direction::East), count(Direction::West));
+
+    let max_vertical = north.max(south);
+    let max_horizontal = east.max(west);
+    let offset_north = compute_offset(north, south, max_horizontal);
+    let offset_south = compute_offset(south, north, max_horizontal);
+    let offset_east = compute_offset(east, west, max_vertical);
+    let offset_west = compute_offset(west, east, max_vertical);
+    let width = compute_dimension(max_vertical, max_horizontal);
+    let height = compute_dimension(max_horizontal, max_vertical);
+
+    let anchor = match facing {
+        Direction::East => (width, offset_east),
+        Direction::West => (0, offset_west),
+        Direction::North => (offset_north, 0),
+        Direction::South => (offset_south, height),
+    };
+
+    // Pins along the west and east edges are ordered by y first, the others by x first
+    let mut ordered: Vec<usize> = (0..pins.len()).collect();
+    ordered.sort_by_key(|&i| {
+        let location = pins[i].location;
+        match edge(&pins[i]) {
+            Direction::East | Direction::West => (location.y, location.x),
+            _ => (location.x, location.y),
+        }
+    });
+
+    let mut offsets = vec![(0, 0); pins.len()];
+    let mut placed = [0; 4];
+    for i in ordered {
+        let direction = edge(&pins[i]);
+        let n = &mut placed[direction.quarter_turns() as usize];
+        let port = match direction {
+            Direction::North => (offset_north + 10 * *n, 0),
+            Direction::South => (offset_south + 10 * *n, height),
+            Direction::East => (width, offset_east + 10 * *n),
+            Direction::West => (0, offset_west + 10 * *n),
+        };
+        *n += 1;
+        offsets[i] = (port.0 - anchor.0, port.1 - anchor.1);
+    }
+
+    offsets
+}
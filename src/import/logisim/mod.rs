@@ -0,0 +1,622 @@
+mod geometry;
+pub use geometry::Location;
+
+use std::collections::HashMap;
+use rassert_rs::rassert;
+
+use crate::circuit::{CircuitDefinition, Registry};
+use crate::circuit::registry::{CLOCK_ID, LED_ID, SWITCH_ID};
+use crate::component::definition::{Pins, Signal, TransparentBuilder};
+use super::ImportError;
+use super::xml::{self, Element};
+use geometry::{Appearance, Direction, GateShape, PinShape};
+
+/// A Logisim project converted into a registry and a top-level circuit.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogisimImport {
+    /// The builtin definitions and a transparent definition for every subcircuit.
+    pub registry: Registry,
+    /// The main circuit, with input pins as switches and output pins as LEDs.
+    pub circuit: CircuitDefinition,
+    pub log: Vec<ConversionEntry>,
+}
+
+/// An element of the Logisim project which could not be converted faithfully.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionEntry {
+    pub circuit: String,
+    pub component: String,
+    pub location: Location,
+    pub issue: ConversionIssue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum ConversionIssue {
+    /// The component was skipped.
+    UnsupportedComponent,
+    /// The component was skipped because of an attribute value, e.g. a bus width.
+    UnsupportedAttribute { name: String, value: String },
+    /// The attribute was ignored, the component was converted without it.
+    IgnoredAttribute { name: String, value: String },
+    /// The port of a subcircuit instance could not be located and is left unconnected.
+    UnresolvedPort { pin: String },
+}
+
+/// A parsed `<circuit>` element.
+struct Sheet<'a> {
+    name: String,
+    element: &'a Element,
+    appearance: Appearance,
+    /// Input pins followed by output pins, both ordered by location.
+    pins: Vec<(PinShape, String)>,
+}
+
+enum GateKind {
+    And,
+    Or,
+    Xor,
+}
+
+/// Imports a Logisim (Evolution) `.circ` project.
+///
+/// Every circuit other than the main one is converted into a transparent definition, with IDs
+/// assigned consecutively from `first_id` in document order. Basic gates are decomposed into
+/// NAND gates, while components which cannot be converted are skipped and reported in the
+/// conversion log.
+pub fn import_logisim(source: &str, first_id: i32) -> Result<LogisimImport, ImportError> {
+    let root = xml::parse(source)?;
+    rassert!(root.name == "project", ImportError::InvalidDocument("Expected a Logisim project".into()));
+
+    // Logisim 2.x defaults to five gate inputs and the classic subcircuit appearance
+    let legacy = root.attr("source").is_none_or(|x| x.starts_with("1.") || x.starts_with("2."));
+    let libraries: HashMap<&str, &str> = root.children_named("lib")
+        .filter_map(|x| Some((x.attr("name")?, x.attr("desc")?)))
+        .collect();
+
+    let sheets: Vec<Sheet> = root.children_named("circuit").map(|x| Sheet::parse(x, legacy)).collect();
+    rassert!(!sheets.is_empty(), ImportError::InvalidDocument("Project contains no circuits".into()));
+    let main = root.children_named("main")
+        .find_map(|x| x.attr("name"))
+        .unwrap_or(&sheets[0].name)
+        .to_string();
+    let main = sheets.iter().position(|x| x.name == main)
+        .ok_or_else(|| ImportError::InvalidDocument(format!("Main circuit '{}' does not exist", main)))?;
+
+    let ids: HashMap<&str, i32> = sheets.iter()
+        .enumerate()
+        .filter(|(i, _)| *i != main)
+        .enumerate()
+        .map(|(n, (_, sheet))| (sheet.name.as_str(), first_id + n as i32))
+        .collect();
+    let mut converter = Converter { sheets: &sheets, ids, libraries, legacy, log: Vec::new() };
+
+    let mut registry = Registry::default();
+    for (i, sheet) in sheets.iter().enumerate() {
+        if i == main {
+            continue;
+        }
+
+        let (builder, outputs) = converter.convert(sheet, false);
+        let pins = Pins {
+            input: sheet.pins.iter().filter(|x| !x.0.output).map(|x| x.1.clone()).collect(),
+            output: sheet.pins.iter().filter(|x| x.0.output).map(|x| x.1.clone()).collect(),
+        };
+        registry.try_insert(builder.build(converter.ids[sheet.name.as_str()], sheet.name.clone(), pins, &outputs))?;
+    }
+
+    let (builder, _) = converter.convert(&sheets[main], true);
+    let def = builder.build(0, sheets[main].name.clone(), Pins { input: Vec::new(), output: Vec::new() }, &[]);
+    let inner = def.circuit.unwrap();
+    let circuit = CircuitDefinition {
+        name: def.name,
+        components: inner.components,
+        connections: inner.connections,
+        ..Default::default()
+    };
+
+    Ok(LogisimImport { registry, circuit, log: converter.log })
+}
+
+impl<'a> Sheet<'a> {
+    fn parse(element: &'a Element, legacy: bool) -> Self {
+        let name = element.attr("name").unwrap_or_default().to_string();
+        let circuit_attributes = attributes(element);
+
+        let mut pins: Vec<(PinShape, String)> = element.children_named("comp")
+            .filter(|x| x.attr("name") == Some("Pin") && x.attr("lib").is_some())
+            .filter_map(|x| {
+                let attributes = attributes(x);
+                if attributes.get("width").is_some_and(|x| *x != "1") {
+                    return None;
+                }
+
+                let shape = PinShape {
+                    location: Location::parse(x.attr("loc")?)?,
+                    facing: Direction::parse(attributes.get("facing").copied()),
+                    output: is_output_pin(&attributes),
+                };
+                Some((shape, attributes.get("label").copied().unwrap_or_default().to_string()))
+            })
+            .collect();
+        pins.sort_by_key(|x| (x.0.output, x.0.location.y, x.0.location.x));
+
+        // Unlabelled pins are named after their position
+        let (mut inputs, mut outputs) = (0, 0);
+        for (shape, label) in pins.iter_mut() {
+            let n = if shape.output { &mut outputs } else { &mut inputs };
+            if label.is_empty() {
+                *label = format!("{}{}", if shape.output { "out" } else { "in" }, n);
+            }
+            *n += 1;
+        }
+
+        let appearance = match element.children_named("appear").next() {
+            Some(appear) => {
+                let anchor = appear.children_named("circ-anchor").next();
+                // Ports and the anchor are stored as boxes around their location
+                let location = |x: &Element| {
+                    let value = |name| x.attr(name).and_then(|x| x.parse::<i32>().ok());
+                    Some(Location {
+                        x: value("x")? + value("width").unwrap_or(0) / 2,
+                        y: value("y")? + value("height").unwrap_or(0) / 2,
+                    })
+                };
+                Appearance::Custom {
+                    anchor: anchor.and_then(location).unwrap_or(Location { x: 0, y: 0 }),
+                    facing: Direction::parse(anchor.and_then(|x| x.attr("facing"))),
+                    ports: appear.children_named("circ-port")
+                        .filter_map(|x| Some((Location::parse(x.attr("pin")?)?, location(x)?)))
+                        .collect(),
+                }
+            },
+            None => match circuit_attributes.get("appearance").copied() {
+                Some("classic") => Appearance::Classic,
+                Some(_) => Appearance::Evolution,
+                None if legacy => Appearance::Classic,
+                None => Appearance::Evolution,
+            },
+        };
+
+        Self { name, element, appearance, pins }
+    }
+}
+
+struct Converter<'a> {
+    sheets: &'a [Sheet<'a>],
+    ids: HashMap<&'a str, i32>,
+    libraries: HashMap<&'a str, &'a str>,
+    legacy: bool,
+    log: Vec<ConversionEntry>,
+}
+
+impl<'a> Converter<'a> {
+    /// Converts the circuit's components into a builder, returning the signals of the output pins.
+    ///
+    /// Pins of the top-level circuit are converted into switches and LEDs instead.
+    fn convert(&mut self, sheet: &Sheet, top_level: bool) -> (TransparentBuilder, Vec<Signal>) {
+        let wires: Vec<(Location, Location)> = sheet.element.children_named("wire")
+            .filter_map(|x| Some((Location::parse(x.attr("from")?)?, Location::parse(x.attr("to")?)?)))
+            .collect();
+        let wire_ends: Vec<Location> = wires.iter().flat_map(|x| [x.0, x.1]).collect();
+
+        let mut nets = Nets::default();
+        for &(from, to) in wires.iter() {
+            nets.union(from, to);
+        }
+
+        // Resolve the ports of every component first, so that all of their locations are known
+        // when joining the nets
+        let mut parts = Vec::new();
+        let mut tunnels: HashMap<String, Location> = HashMap::new();
+        for comp in sheet.element.children_named("comp") {
+            let Some(location) = comp.attr("loc").and_then(Location::parse) else {
+                continue;
+            };
+            let attributes = attributes(comp);
+            let name = comp.attr("name").unwrap_or_default();
+            let library = comp.attr("lib").map(|x| self.libraries.get(x).copied().unwrap_or_default());
+            let mut issues = Vec::new();
+
+            let facing = Direction::parse(attributes.get("facing").copied());
+            let part = match (library, name) {
+                _ if attributes.get("width").is_some_and(|x| *x != "1") => {
+                    issues.push(ConversionIssue::UnsupportedAttribute { name: "width".into(), value: attributes["width"].into() });
+                    None
+                },
+                (Some("#Wiring"), "Pin") => Some(Part::Pin { output: is_output_pin(&attributes) }),
+                (Some("#Wiring"), "Clock") => {
+                    for attribute in ["highDuration", "lowDuration"] {
+                        if let Some(value) = attributes.get(attribute).filter(|x| **x != "1") {
+                            issues.push(ConversionIssue::IgnoredAttribute { name: attribute.into(), value: value.to_string() });
+                        }
+                    }
+                    Some(Part::Clock)
+                },
+                (Some("#Wiring"), "Constant") => {
+                    let value = attributes.get("value").copied().unwrap_or("0x1");
+                    let value = value.strip_prefix("0x").map(|x| u32::from_str_radix(x, 16)).unwrap_or_else(|| value.parse());
+                    Some(Part::Constant(value != Ok(0)))
+                },
+                (Some("#Wiring"), "Power") => Some(Part::Constant(true)),
+                (Some("#Wiring"), "Ground") => Some(Part::Constant(false)),
+                (Some("#Wiring"), "Tunnel") => {
+                    let label = attributes.get("label").copied().unwrap_or_default();
+                    match tunnels.get(label) {
+                        Some(&other) => nets.union(location, other),
+                        None => {
+                            tunnels.insert(label.into(), location);
+                        },
+                    }
+                    None
+                },
+                (Some("#I/O"), "LED") => Some(Part::Led),
+                (Some("#Base"), "Text") => None,
+                (Some("#Gates"), "AND Gate" | "OR Gate" | "XOR Gate" | "NAND Gate" | "NOR Gate" | "XNOR Gate") => {
+                    let kind = match name {
+                        "AND Gate" | "NAND Gate" => GateKind::And,
+                        "OR Gate" | "NOR Gate" => GateKind::Or,
+                        _ => GateKind::Xor,
+                    };
+                    let shape = GateShape {
+                        facing,
+                        size: attributes.get("size").and_then(|x| x.parse().ok()).unwrap_or(50),
+                        inputs: attributes.get("inputs").and_then(|x| x.parse().ok()).unwrap_or(if self.legacy { 5 } else { 2 }),
+                        bonus_width: if matches!(kind, GateKind::Xor) { 10 } else { 0 },
+                        negate_output: name.starts_with('N') || name.starts_with("XN"),
+                    };
+                    let negated: Vec<bool> = (0..shape.inputs)
+                        .map(|i| attributes.get(format!("negate{}", i).as_str()) == Some(&"true"))
+                        .collect();
+                    let inputs = negated.iter().enumerate()
+                        .map(|(i, &negated)| location.translate(shape.input_offset(i, negated)))
+                        .collect();
+
+                    Some(Part::Gate {
+                        kind,
+                        inputs,
+                        negated,
+                        negate_output: shape.negate_output,
+                        parity: attributes.get("xor") == Some(&"odd"),
+                    })
+                },
+                (Some("#Gates"), "NOT Gate") => {
+                    let size = attributes.get("size").and_then(|x| x.parse().ok()).unwrap_or(30);
+                    Some(Part::Not(location.translate(facing.rotate((-size, 0)))))
+                },
+                (Some("#Gates"), "Buffer") => Some(Part::Buffer(location.translate(facing.rotate((-20, 0))))),
+                (Some("#Gates"), "Controlled Buffer") => {
+                    let side = if attributes.get("control") == Some(&"left") { -10 } else { 10 };
+                    Some(Part::ControlledBuffer(location.translate(facing.rotate((-20, 0))), location.translate(facing.rotate((-10, side)))))
+                },
+                (None, _) => match self.sheets.iter().find(|x| x.name == name) {
+                    Some(sub) if self.ids.contains_key(sub.name.as_str()) => {
+                        let ports = self.port_locations(sub, location, facing, &wire_ends);
+                        for (port, (_, pin)) in ports.iter().zip(sub.pins.iter()) {
+                            if port.is_none() {
+                                issues.push(ConversionIssue::UnresolvedPort { pin: pin.clone() });
+                            }
+                        }
+                        Some(Part::Subcircuit { id: self.ids[sub.name.as_str()], ports, num_inputs: sub.pins.iter().filter(|x| !x.0.output).count() })
+                    },
+                    _ => {
+                        issues.push(ConversionIssue::UnsupportedComponent);
+                        None
+                    },
+                },
+                _ => {
+                    issues.push(ConversionIssue::UnsupportedComponent);
+                    None
+                },
+            };
+
+            self.log.extend(issues.into_iter().map(|issue| ConversionEntry {
+                circuit: sheet.name.clone(),
+                component: name.into(),
+                location,
+                issue,
+            }));
+            if let Some(part) = part {
+                parts.push((location, part));
+            }
+        }
+
+        // Ports touching the middle of a wire are connected to it
+        let mut points: Vec<Location> = wire_ends.clone();
+        points.extend(parts.iter().flat_map(|(location, part)| part.locations(*location)));
+        points.extend(tunnels.values());
+        for &point in points.iter() {
+            for &(from, to) in wires.iter() {
+                if on_segment(point, from, to) {
+                    nets.union(point, from);
+                }
+            }
+        }
+
+        let mut builder = TransparentBuilder::default();
+        let mut signals: HashMap<Location, Signal> = HashMap::new();
+        let mut net = |builder: &mut TransparentBuilder, location: Location| {
+            *signals.entry(nets.find(location)).or_insert_with(|| builder.net())
+        };
+        let mut outputs = Vec::new();
+        let num_inputs = sheet.pins.iter().filter(|x| !x.0.output).count();
+        let output_pins: Vec<Location> = sheet.pins[num_inputs..].iter().map(|x| x.0.location).collect();
+
+        for (location, part) in parts {
+            match part {
+                Part::Pin { output: false } => {
+                    let signal = if top_level {
+                        builder.component(SWITCH_ID, &[], 1)[0]
+                    } else {
+                        Signal::Input(sheet.pins.iter().position(|x| x.0.location == location && !x.0.output).unwrap())
+                    };
+                    let net = net(&mut builder, location);
+                    builder.drive(net, signal);
+                },
+                Part::Pin { output: true } => {
+                    let net = net(&mut builder, location);
+                    if top_level {
+                        builder.component(LED_ID, &[net], 0);
+                    } else {
+                        outputs.push((location, net));
+                    }
+                },
+                Part::Led => {
+                    let net = net(&mut builder, location);
+                    builder.component(LED_ID, &[net], 0);
+                },
+                Part::Clock => {
+                    let signal = builder.component(CLOCK_ID, &[], 1)[0];
+                    let net = net(&mut builder, location);
+                    builder.drive(net, signal);
+                },
+                Part::Constant(value) => {
+                    let signal = builder.constant(value);
+                    let net = net(&mut builder, location);
+                    builder.drive(net, signal);
+                },
+                Part::Gate { kind, inputs, negated, negate_output, parity } => {
+                    // Unconnected inputs are ignored by Logisim
+                    let signals: Vec<Signal> = inputs.iter().zip(negated)
+                        .filter(|(x, _)| nets.is_connected(**x))
+                        .map(|(&x, negated)| {
+                            let signal = net(&mut builder, x);
+                            if negated { builder.not(signal) } else { signal }
+                        })
+                        .collect();
+                    if signals.is_empty() {
+                        continue;
+                    }
+
+                    let mut signal = match kind {
+                        GateKind::And => builder.and(&signals),
+                        GateKind::Or => builder.or(&signals),
+                        GateKind::Xor if parity || signals.len() == 2 => builder.xor(&signals),
+                        GateKind::Xor => exactly_one(&mut builder, &signals),
+                    };
+                    if negate_output {
+                        signal = builder.not(signal);
+                    }
+                    let net = net(&mut builder, location);
+                    builder.drive(net, signal);
+                },
+                Part::Not(input) => {
+                    let input = net(&mut builder, input);
+                    let signal = builder.not(input);
+                    let net = net(&mut builder, location);
+                    builder.drive(net, signal);
+                },
+                Part::Buffer(input) => {
+                    let input = net(&mut builder, input);
+                    let net = net(&mut builder, location);
+                    builder.drive(net, input);
+                },
+                Part::ControlledBuffer(input, control) => {
+                    let input = net(&mut builder, input);
+                    let control = net(&mut builder, control);
+                    let signal = builder.tristate(input, control);
+                    let net = net(&mut builder, location);
+                    builder.drive(net, signal);
+                },
+                Part::Subcircuit { id, ports, num_inputs } => {
+                    let inputs: Vec<Signal> = ports[..num_inputs].iter()
+                        .map(|x| match x {
+                            Some(x) => net(&mut builder, location.translate(*x)),
+                            None => builder.net(),
+                        })
+                        .collect();
+                    let signals = builder.component(id, &inputs, ports.len() - num_inputs);
+                    for (port, signal) in ports[num_inputs..].iter().zip(signals) {
+                        if let Some(port) = port {
+                            let net = net(&mut builder, location.translate(*port));
+                            builder.drive(net, signal);
+                        }
+                    }
+                },
+            }
+        }
+
+        let outputs = output_pins.iter()
+            .map(|pin| outputs.iter().find(|x| x.0 == *pin).map(|x| x.1).unwrap_or_else(|| builder.net()))
+            .collect();
+        (builder, outputs)
+    }
+
+    /// Locates the ports of a subcircuit instance relative to its location.
+    ///
+    /// The default Logisim Evolution appearance sizes its box by the rendered labels, so the
+    /// width is inferred from the wires ending on the box's input edge.
+    fn port_locations(&self, sub: &Sheet, location: Location, facing: Direction, wire_ends: &[Location]) -> Vec<Option<(i32, i32)>> {
+        let pins: Vec<PinShape> = sub.pins.iter().map(|x| x.0.clone()).collect();
+        if !matches!(sub.appearance, Appearance::Evolution) || pins.iter().all(|x| x.output) {
+            return sub.appearance.port_offsets(&pins, facing, 0);
+        }
+
+        let hits = |offsets: &[Option<(i32, i32)>]| pins.iter().zip(offsets)
+            .filter(|(pin, offset)| !pin.output && offset.is_some_and(|x| wire_ends.contains(&location.translate(x))))
+            .count();
+        let best = (1..=50).map(|x| x * 10)
+            .map(|width| sub.appearance.port_offsets(&pins, facing, width))
+            .max_by_key(|offsets| (hits(offsets), std::cmp::Reverse(offsets.iter().flatten().map(|x| x.0.abs() + x.1.abs()).sum::<i32>())))
+            .unwrap();
+
+        if hits(&best) > 0 {
+            best
+        } else {
+            best.into_iter().zip(pins.iter()).map(|(x, pin)| x.filter(|_| pin.output)).collect()
+        }
+    }
+}
+
+enum Part {
+    Pin { output: bool },
+    Clock,
+    Constant(bool),
+    Led,
+    Gate { kind: GateKind, inputs: Vec<Location>, negated: Vec<bool>, negate_output: bool, parity: bool },
+    Not(Location),
+    Buffer(Location),
+    ControlledBuffer(Location, Location),
+    Subcircuit { id: i32, ports: Vec<Option<(i32, i32)>>, num_inputs: usize },
+}
+
+impl Part {
+    /// Returns the locations of all the part's ports.
+    fn locations(&self, location: Location) -> Vec<Location> {
+        let mut locations = vec![location];
+        match self {
+            Self::Gate { inputs, .. } => locations.extend(inputs),
+            Self::Not(input) | Self::Buffer(input) => locations.push(*input),
+            Self::ControlledBuffer(input, control) => locations.extend([*input, *control]),
+            Self::Subcircuit { ports, .. } => {
+                locations = ports.iter().flatten().map(|x| location.translate(*x)).collect();
+            },
+            _ => {},
+        }
+        locations
+    }
+}
+
+/// Groups connected locations, using a union-find structure.
+#[derive(Default)]
+struct Nets {
+    parents: HashMap<Location, Location>,
+    sizes: HashMap<Location, usize>,
+}
+
+impl Nets {
+    fn find(&self, mut location: Location) -> Location {
+        while let Some(&parent) = self.parents.get(&location) {
+            location = parent;
+        }
+        location
+    }
+
+    fn union(&mut self, a: Location, b: Location) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents.insert(a, b);
+            let size = self.sizes.remove(&a).unwrap_or(1);
+            *self.sizes.entry(b).or_insert(1) += size;
+        }
+    }
+
+    /// Whether the location is connected to anything but itself.
+    fn is_connected(&self, location: Location) -> bool {
+        let root = self.find(location);
+        self.sizes.get(&root).copied().unwrap_or(1) > 1
+    }
+}
+
+/// Returns the `<a name=".." val=".."/>` attributes of the element.
+fn attributes(element: &Element) -> HashMap<&str, &str> {
+    element.children_named("a")
+        .filter_map(|x| Some((x.attr("name")?, x.attr("val").unwrap_or(x.text.as_str()))))
+        .collect()
+}
+
+fn is_output_pin(attributes: &HashMap<&str, &str>) -> bool {
+    attributes.get("output") == Some(&"true") || attributes.get("type") == Some(&"output")
+}
+
+fn on_segment(point: Location, from: Location, to: Location) -> bool {
+    let within = |x: i32, a: i32, b: i32| a.min(b) <= x && x <= a.max(b);
+    (from.x == to.x && point.x == from.x && within(point.y, from.y, to.y))
+        || (from.y == to.y && point.y == from.y && within(point.x, from.x, to.x))
+}
+
+/// Returns a signal which is high when exactly one of the inputs is high.
+fn exactly_one(builder: &mut TransparentBuilder, inputs: &[Signal]) -> Signal {
+    let terms: Vec<Signal> = (0..inputs.len())
+        .map(|i| {
+            let term: Vec<Signal> = inputs.iter()
+                .enumerate()
+                .map(|(j, &x)| if i == j { x } else { builder.not(x) })
+                .collect();
+            builder.and(&term)
+        })
+        .collect();
+    builder.or(&terms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Simulation;
+    use crate::circuit::Circuit;
+    use crate::component::{Led, Switch};
+
+    #[test]
+    fn gate_input_offsets() {
+        let shape = |inputs, negate_output| GateShape { facing: Direction::East, size: 50, inputs, bonus_width: 0, negate_output };
+        let offsets: Vec<_> = (0..2).map(|i| shape(2, true).input_offset(i, false)).collect();
+        assert_eq!(offsets, vec![(-60, -20), (-60, 20)]);
+
+        let offsets: Vec<_> = (0..5).map(|i| shape(5, false).input_offset(i, false)).collect();
+        assert_eq!(offsets, vec![(-50, -20), (-50, -10), (-50, 0), (-50, 10), (-50, 20)]);
+
+        let pins = [
+            PinShape { location: Location { x: 0, y: 0 }, facing: Direction::East, output: false },
+            PinShape { location: Location { x: 0, y: 10 }, facing: Direction::East, output: false },
+            PinShape { location: Location { x: 50, y: 0 }, facing: Direction::West, output: true },
+        ];
+        let offsets = Appearance::Classic.port_offsets(&pins, Direction::East, 0);
+        assert_eq!(offsets, vec![Some((-30, 0)), Some((-30, 10)), Some((0, 0))]);
+    }
+
+    #[test]
+    fn half_adder_project() {
+        let import = import_logisim(include_str!("../../../tests/assets/half_adder.circ"), 1).unwrap();
+
+        let def = import.registry.get_definition(1).unwrap();
+        assert_eq!(def.name, "half_adder");
+        assert_eq!(def.pins.input, vec!["A", "B"]);
+        assert_eq!(def.pins.output, vec!["S", "C"]);
+
+        let issues: Vec<_> = import.log.iter().map(|x| (x.component.as_str(), &x.issue)).collect();
+        assert_eq!(issues, vec![
+            ("Probe", &ConversionIssue::UnsupportedComponent),
+            ("Pin", &ConversionIssue::UnsupportedAttribute { name: "width".into(), value: "4".into() }),
+        ]);
+
+        let ids = |def_id| import.circuit.components.iter().filter(|x| x.def_id == def_id).map(|x| x.id).collect::<Vec<_>>();
+        let (switches, leds) = (ids(SWITCH_ID), ids(LED_ID));
+        assert_eq!((switches.len(), leds.len()), (2, 2));
+
+        for (x, y) in [(false, false), (false, true), (true, false), (true, true)] {
+            let mut sim = Simulation {
+                circuit: Circuit::from_definition(&import.registry, import.circuit.clone()).unwrap(),
+                ..Default::default()
+            };
+            sim.circuit.components.get_mut(&switches[0]).unwrap().as_any_mut().downcast_mut::<Switch>().unwrap().output = x;
+            sim.circuit.components.get_mut(&switches[1]).unwrap().as_any_mut().downcast_mut::<Switch>().unwrap().output = y;
+            sim.init();
+            sim.tick_for(32);
+
+            let led = |id| sim.circuit.components[&id].as_any().downcast_ref::<Led>().unwrap().value;
+            assert_eq!((led(leds[0]), led(leds[1])), (x ^ y, x && y), "x = {}, y = {}", x, y);
+        }
+    }
+}
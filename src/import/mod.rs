@@ -0,0 +1,64 @@
+mod verilog;
+mod logisim;
+mod xml;
+pub use verilog::import_verilog;
+pub use logisim::{import_logisim, ConversionEntry, ConversionIssue, Location, LogisimImport};
+
+use crate::circuit::DefinitionError;
+use crate::circuit::registry::REGISTRY;
+use crate::wasm;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("Syntax error on line {line}: {message}.")]
+    SyntaxError { line: usize, message: String },
+
+    #[error("Module '{0}' is neither declared nor a known definition.")]
+    UnknownModule(String),
+
+    #[error("Module '{0}' matches more than one definition: {1:?}.")]
+    AmbiguousModule(String, Vec<i32>),
+
+    #[error("Gate '{0}' is not supported.")]
+    UnsupportedGate(String),
+
+    #[error("Module '{0}' has no port named '{1}'.")]
+    UnknownPort(String, String),
+
+    #[error("Output '{1}' of module '{0}' is never driven.")]
+    UndrivenOutput(String, String),
+
+    #[error("Input '{1}' of module '{0}' cannot be driven inside the module.")]
+    DrivenInput(String, String),
+
+    #[error("Invalid document: {0}.")]
+    InvalidDocument(String),
+
+    #[error("Encountered a definition error.")]
+    DefinitionError(#[from] DefinitionError),
+}
+
+/// Imports a structural Verilog source, instantiating modules of the current registry.
+///
+/// The imported definitions are returned, but not inserted into the registry.
+#[wasm::wasm_bindgen]
+pub fn import_verilog_definitions(source: String, first_id: i32) -> Result<wasm::JsValue, String> {
+    let definitions = REGISTRY.with(|reg| import_verilog(&source, &reg.lock(), first_id)).map_err(|e| e.to_string())?;
+    Ok(wasm::JsValue::from_serde(&definitions).unwrap())
+}
+
+/// Imports a Logisim project, replacing the current registry with the converted definitions.
+///
+/// # Returns
+/// The converted main circuit and the conversion log.
+#[wasm::wasm_bindgen]
+pub fn import_logisim_project(source: String, first_id: i32) -> Result<wasm::JsValue, String> {
+    let import = import_logisim(&source, first_id).map_err(|e| e.to_string())?;
+    let result = wasm::JsValue::from_serde(&serde_json::json!({
+        "circuit": import.circuit,
+        "log": import.log,
+    })).unwrap();
+    REGISTRY.with(|reg| reg.lock().replace(import.registry));
+
+    Ok(result)
+}
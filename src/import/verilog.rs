@@ -0,0 +1,675 @@
+use std::collections::HashMap;
+use rassert_rs::rassert;
+
+use crate::circuit::Registry;
+use crate::component::ComponentDefinition;
+use crate::component::definition::{Pins, Signal, TransparentBuilder};
+use crate::export::{port_names, sanitize};
+use super::ImportError;
+use ImportError::*;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Constant(bool),
+    Symbol(char),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Net(String),
+    Constant(bool),
+    Not(Box<Expr>),
+}
+
+#[derive(Debug)]
+enum Item {
+    Assign { target: String, expr: Expr },
+    Gate { kind: String, terminals: Vec<Expr> },
+    Instance { module: String, connections: Connections },
+}
+
+#[derive(Debug)]
+enum Connections {
+    Named(Vec<(String, Option<Expr>)>),
+    Positional(Vec<Expr>),
+}
+
+#[derive(Debug, Default)]
+struct Module {
+    name: String,
+    ports: Vec<String>,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    items: Vec<(Item, usize)>,
+}
+
+/// Imports the modules of a gate-level structural Verilog source as transparent definitions.
+///
+/// Supported are the `and`, `nand`, `or`, `nor`, `xor`, `xnor`, `not`, `buf`, `bufif0`, `bufif1`,
+/// `notif0` and `notif1` primitives, `assign` statements of (inverted) nets and constants, as well
+/// as instances of modules declared earlier in the source or of registry definitions with a
+/// matching name. Gates other than `nand` and `bufif1` are decomposed into NAND gates, and the
+/// other tristate gates into a tristate with inverters on its data or enable input.
+///
+/// # Returns
+/// The definitions in source order, with IDs assigned consecutively from `first_id`.
+pub fn import_verilog(source: &str, registry: &Registry, first_id: i32) -> Result<Vec<ComponentDefinition>, ImportError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, pos: 0 };
+
+    let mut definitions: Vec<ComponentDefinition> = Vec::new();
+    while !parser.at_end() {
+        let module = parser.module()?;
+        let id = first_id + definitions.len() as i32;
+        let def = elaborate(module, id, &definitions, registry)?;
+        definitions.push(def);
+    }
+
+    Ok(definitions)
+}
+
+/// Finds the registry definition named like the module, which has to be unique since the registry
+/// is not ordered.
+fn registry_module<'a>(registry: &'a Registry, name: &str) -> Result<&'a ComponentDefinition, ImportError> {
+    let mut matching: Vec<&ComponentDefinition> = registry.definitions().filter(|x| sanitize(&x.name) == name).collect();
+    match matching.len() {
+        0 => Err(UnknownModule(name.into())),
+        1 => Ok(matching[0]),
+        _ => {
+            matching.sort_by_key(|x| x.id);
+            Err(AmbiguousModule(name.into(), matching.into_iter().map(|x| x.id).collect()))
+        },
+    }
+}
+
+fn elaborate(module: Module, id: i32, modules: &[ComponentDefinition], registry: &Registry) -> Result<ComponentDefinition, ImportError> {
+    let mut builder = TransparentBuilder::default();
+    let mut nets: HashMap<String, Signal> = HashMap::new();
+    for (i, input) in module.inputs.iter().enumerate() {
+        nets.insert(input.clone(), Signal::Input(i));
+    }
+
+    let mut driven = Vec::new();
+    for (item, line) in module.items.iter() {
+        match item {
+            Item::Assign { target, expr } => {
+                let signal = signal(&mut builder, &mut nets, expr);
+                drive(&mut builder, &mut nets, &module, target, signal)?;
+                driven.push(target.clone());
+            },
+            Item::Gate { kind, terminals } => {
+                let (outputs, inputs) = match kind.as_str() {
+                    "not" | "buf" => terminals.split_at(terminals.len() - 1),
+                    _ => terminals.split_at(1),
+                };
+                let inputs: Vec<Signal> = inputs.iter().map(|x| signal(&mut builder, &mut nets, x)).collect();
+
+                let result = match kind.as_str() {
+                    "and" => builder.and(&inputs),
+                    "nand" if inputs.len() == 2 => builder.nand(inputs[0], inputs[1]),
+                    "nand" => {
+                        let and = builder.and(&inputs);
+                        builder.not(and)
+                    },
+                    "or" => builder.or(&inputs),
+                    "nor" => {
+                        let or = builder.or(&inputs);
+                        builder.not(or)
+                    },
+                    "xor" => builder.xor(&inputs),
+                    "xnor" => {
+                        let xor = builder.xor(&inputs);
+                        builder.not(xor)
+                    },
+                    "not" => builder.not(inputs[0]),
+                    "buf" => inputs[0],
+                    "bufif0" | "bufif1" | "notif0" | "notif1" if inputs.len() == 2 => {
+                        let data = if kind.starts_with("notif") { builder.not(inputs[0]) } else { inputs[0] };
+                        let enable = if kind.ends_with('0') { builder.not(inputs[1]) } else { inputs[1] };
+                        builder.tristate(data, enable)
+                    },
+                    _ => return Err(UnsupportedGate(kind.clone())),
+                };
+
+                for output in outputs {
+                    let Expr::Net(output) = output else {
+                        return Err(SyntaxError { line: *line, message: format!("Gate '{}' drives a constant", kind) });
+                    };
+                    drive(&mut builder, &mut nets, &module, output, result)?;
+                    driven.push(output.clone());
+                }
+            },
+            Item::Instance { module: name, connections } => {
+                let def = match modules.iter().rev().find(|x| sanitize(&x.name) == *name) {
+                    Some(def) => def,
+                    None => registry_module(registry, name)?,
+                };
+                let ports = port_names(&def.pins);
+                let num_inputs = def.pins.input.len();
+
+                // Order the connected expressions by the definition's pins
+                let mut bound: Vec<Option<Expr>> = vec![None; ports.len()];
+                match connections {
+                    Connections::Named(named) => {
+                        for (port, expr) in named.iter() {
+                            let pin = ports.iter().position(|x| x == port).ok_or_else(|| UnknownPort(name.clone(), port.clone()))?;
+                            bound[pin] = expr.clone();
+                        }
+                    },
+                    Connections::Positional(positional) => {
+                        rassert!(positional.len() <= ports.len(), SyntaxError {
+                            line: *line,
+                            message: format!("Too many connections for module '{}'", name),
+                        });
+                        positional.iter().enumerate().for_each(|(pin, expr)| bound[pin] = Some(expr.clone()));
+                    },
+                }
+
+                let inputs: Vec<Signal> = bound[..num_inputs].iter()
+                    .map(|expr| match expr {
+                        Some(expr) => signal(&mut builder, &mut nets, expr),
+                        None => builder.net(),
+                    })
+                    .collect();
+                let outputs = builder.component(def.id, &inputs, ports.len() - num_inputs);
+
+                for (expr, output) in bound[num_inputs..].iter().zip(outputs) {
+                    match expr {
+                        Some(Expr::Net(net)) => {
+                            drive(&mut builder, &mut nets, &module, net, output)?;
+                            driven.push(net.clone());
+                        },
+                        Some(_) => return Err(SyntaxError { line: *line, message: format!("Output of module '{}' drives an expression", name) }),
+                        None => {},
+                    }
+                }
+            },
+        }
+    }
+
+    let mut outputs = Vec::with_capacity(module.outputs.len());
+    for output in module.outputs.iter() {
+        rassert!(driven.contains(output), UndrivenOutput(module.name.clone(), output.clone()));
+        outputs.push(nets[output]);
+    }
+
+    let pins = Pins {
+        input: module.inputs.clone(),
+        output: module.outputs.clone(),
+    };
+    Ok(builder.build(id, module.name, pins, &outputs))
+}
+
+/// Returns the signal of the expression, declaring nets as they are encountered.
+fn signal(builder: &mut TransparentBuilder, nets: &mut HashMap<String, Signal>, expr: &Expr) -> Signal {
+    match expr {
+        Expr::Net(name) => *nets.entry(name.clone()).or_insert_with(|| builder.net()),
+        Expr::Constant(value) => builder.constant(*value),
+        Expr::Not(expr) => {
+            let signal = signal(builder, nets, expr);
+            builder.not(signal)
+        },
+    }
+}
+
+fn drive(builder: &mut TransparentBuilder, nets: &mut HashMap<String, Signal>, module: &Module, name: &str, driver: Signal) -> Result<(), ImportError> {
+    rassert!(!module.inputs.iter().any(|x| x == name), DrivenInput(module.name.clone(), name.into()));
+
+    let net = *nets.entry(name.into()).or_insert_with(|| builder.net());
+    builder.drive(net, driver);
+    Ok(())
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ImportError> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = source.chars().collect();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\n' => {
+                line += 1;
+                i += 1;
+            },
+            c if c.is_whitespace() => i += 1,
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            },
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    line += (chars[i] == '\n') as usize;
+                    i += 1;
+                }
+                i += 2;
+            },
+            c if c.is_ascii_alphabetic() || c == '_' || c == '\\' => {
+                // Escaped identifiers run until the next whitespace
+                let escaped = c == '\\';
+                let start = i + escaped as usize;
+                i += 1;
+                while i < chars.len() && (if escaped { !chars[i].is_whitespace() } else { chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '$' }) {
+                    i += 1;
+                }
+                tokens.push((Token::Ident(chars[start..i].iter().collect()), line));
+            },
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '\'') {
+                    i += 1;
+                }
+                let literal: String = chars[start..i].iter().collect();
+                let value = match literal.split_once('\'') {
+                    Some((_, value)) if value.len() > 1 => &value[1..],
+                    Some(_) => return Err(SyntaxError { line, message: format!("Invalid constant '{}'", literal) }),
+                    None => literal.as_str(),
+                };
+                match value.trim_start_matches('0') {
+                    "" => tokens.push((Token::Constant(false), line)),
+                    "1" => tokens.push((Token::Constant(true), line)),
+                    _ => return Err(SyntaxError { line, message: format!("Only single bit constants are supported, found '{}'", literal) }),
+                }
+            },
+            '(' | ')' | ',' | ';' | '.' | '=' | '~' | '!' => {
+                tokens.push((Token::Symbol(c), line));
+                i += 1;
+            },
+            '[' => return Err(SyntaxError { line, message: "Vectors are not supported".into() }),
+            c => return Err(SyntaxError { line, message: format!("Unexpected character '{}'", c) }),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.pos).or(self.tokens.last()).map(|x| x.1).unwrap_or(1)
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ImportError> {
+        Err(SyntaxError { line: self.line(), message: message.into() })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|x| &x.0)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|x| x.0.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn is_symbol(&self, symbol: char) -> bool {
+        self.peek() == Some(&Token::Symbol(symbol))
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(x)) if x == keyword)
+    }
+
+    fn symbol(&mut self, symbol: char) -> Result<(), ImportError> {
+        match self.next() {
+            Some(Token::Symbol(x)) if x == symbol => Ok(()),
+            _ => {
+                self.pos -= 1;
+                self.error(format!("Expected '{}'", symbol))
+            },
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, ImportError> {
+        match self.next() {
+            Some(Token::Ident(x)) => Ok(x),
+            _ => {
+                self.pos -= 1;
+                self.error("Expected an identifier")
+            },
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), ImportError> {
+        if self.is_keyword(keyword) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            self.error(format!("Expected '{}'", keyword))
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, ImportError> {
+        match self.next() {
+            Some(Token::Symbol('~' | '!')) => Ok(Expr::Not(Box::new(self.expr()?))),
+            Some(Token::Symbol('(')) => {
+                let expr = self.expr()?;
+                self.symbol(')')?;
+                Ok(expr)
+            },
+            Some(Token::Ident(x)) => Ok(Expr::Net(x)),
+            Some(Token::Constant(x)) => Ok(Expr::Constant(x)),
+            _ => {
+                self.pos -= 1;
+                self.error("Expected a net or a constant")
+            },
+        }
+    }
+
+    fn module(&mut self) -> Result<Module, ImportError> {
+        let mut module = Module::default();
+        self.keyword("module")?;
+        module.name = self.ident()?;
+
+        // Port list, either plain or with ANSI style directions
+        if self.is_symbol('(') {
+            self.symbol('(')?;
+            let mut direction = None;
+            while !self.is_symbol(')') {
+                if self.is_keyword("input") || self.is_keyword("output") {
+                    direction = Some(self.ident()?);
+                }
+                if self.is_keyword("wire") {
+                    self.pos += 1;
+                }
+
+                let port = self.ident()?;
+                match direction.as_deref() {
+                    Some("input") => module.inputs.push(port.clone()),
+                    Some(_) => module.outputs.push(port.clone()),
+                    None => {},
+                }
+                module.ports.push(port);
+
+                if !self.is_symbol(')') {
+                    self.symbol(',')?;
+                }
+            }
+            self.symbol(')')?;
+        }
+        self.symbol(';')?;
+
+        while !self.is_keyword("endmodule") {
+            let line = self.line();
+            let keyword = match self.peek() {
+                Some(Token::Ident(x)) => x.clone(),
+                _ => return self.error("Expected a module item"),
+            };
+
+            match keyword.as_str() {
+                "input" | "output" | "wire" => {
+                    self.pos += 1;
+                    if self.is_keyword("wire") {
+                        self.pos += 1;
+                    }
+                    loop {
+                        let name = self.ident()?;
+                        match keyword.as_str() {
+                            "input" => module.inputs.push(name),
+                            "output" => module.outputs.push(name),
+                            _ => {},
+                        }
+                        if self.is_symbol(';') {
+                            break;
+                        }
+                        self.symbol(',')?;
+                    }
+                    self.symbol(';')?;
+                },
+                "inout" => return self.error("Bidirectional ports are not supported"),
+                "assign" => {
+                    self.pos += 1;
+                    loop {
+                        let target = self.ident()?;
+                        self.symbol('=')?;
+                        let expr = self.expr()?;
+                        module.items.push((Item::Assign { target, expr }, line));
+                        if self.is_symbol(';') {
+                            break;
+                        }
+                        self.symbol(',')?;
+                    }
+                    self.symbol(';')?;
+                },
+                "and" | "nand" | "or" | "nor" | "xor" | "xnor" | "not" | "buf" | "bufif0" | "bufif1" | "notif0" | "notif1" => {
+                    self.pos += 1;
+                    loop {
+                        // Instance names are optional for primitives
+                        if !self.is_symbol('(') {
+                            self.ident()?;
+                        }
+                        self.symbol('(')?;
+                        let mut terminals = vec![self.expr()?];
+                        while self.is_symbol(',') {
+                            self.symbol(',')?;
+                            terminals.push(self.expr()?);
+                        }
+                        self.symbol(')')?;
+
+                        if terminals.len() < 2 {
+                            return self.error(format!("Gate '{}' needs at least two terminals", keyword));
+                        }
+                        module.items.push((Item::Gate { kind: keyword.clone(), terminals }, line));
+
+                        if self.is_symbol(';') {
+                            break;
+                        }
+                        self.symbol(',')?;
+                    }
+                    self.symbol(';')?;
+                },
+                _ => {
+                    self.pos += 1;
+                    self.ident()?;
+                    self.symbol('(')?;
+                    let connections = if self.is_symbol('.') {
+                        let mut named = Vec::new();
+                        while self.is_symbol('.') {
+                            self.symbol('.')?;
+                            let port = self.ident()?;
+                            self.symbol('(')?;
+                            let expr = if self.is_symbol(')') { None } else { Some(self.expr()?) };
+                            self.symbol(')')?;
+                            named.push((port, expr));
+                            if !self.is_symbol(')') {
+                                self.symbol(',')?;
+                            }
+                        }
+                        Connections::Named(named)
+                    } else {
+                        let mut positional = Vec::new();
+                        while !self.is_symbol(')') {
+                            positional.push(self.expr()?);
+                            if !self.is_symbol(')') {
+                                self.symbol(',')?;
+                            }
+                        }
+                        Connections::Positional(positional)
+                    };
+                    self.symbol(')')?;
+                    self.symbol(';')?;
+                    module.items.push((Item::Instance { module: keyword, connections }, line));
+                },
+            }
+        }
+        self.keyword("endmodule")?;
+
+        // Order the pins by the port list
+        for port in module.ports.iter() {
+            if !module.inputs.contains(port) && !module.outputs.contains(port) {
+                return self.error(format!("Port '{}' has no direction", port));
+            }
+        }
+        let position = |x: &String| module.ports.iter().position(|port| port == x).unwrap_or(usize::MAX);
+        let mut inputs = std::mem::take(&mut module.inputs);
+        let mut outputs = std::mem::take(&mut module.outputs);
+        inputs.sort_by_key(position);
+        outputs.sort_by_key(position);
+        module.inputs = inputs;
+        module.outputs = outputs;
+
+        Ok(module)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Simulation;
+    use crate::circuit::{Circuit, CircuitDefinition, Connection, Connector};
+    use crate::circuit::registry::{SWITCH_ID, LED_ID};
+    use crate::component::{Led, Switch};
+    use crate::component::definition::{Component, Pins, Signal, TransparentBuilder};
+    use crate::export::definition_to_verilog;
+
+    fn load_registry() -> Registry {
+        let mut registry = Registry::default();
+        for def in [
+            include_str!("../../tests/assets/and_gate_definition.json"),
+            include_str!("../../tests/assets/not_gate_definition.json"),
+            include_str!("../../tests/assets/ab_inverted_definition.json"),
+        ] {
+            let parsed: ComponentDefinition = serde_json::from_str(def).unwrap();
+            registry.insert(parsed);
+        }
+
+        registry
+    }
+
+    #[test]
+    fn exported_modules_round_trip() {
+        let verilog = definition_to_verilog(&load_registry(), 3).unwrap();
+
+        let mut registry = Registry::default();
+        let definitions = import_verilog(&verilog, &registry, 10).unwrap();
+        let names: Vec<_> = definitions.iter().map(|x| (x.id, x.name.as_str())).collect();
+        assert_eq!(names, vec![(10, "NotGate"), (11, "AndGate"), (12, "AB_Inverted")]);
+
+        definitions.into_iter().for_each(|x| registry.insert(x));
+        assert_eq!(definition_to_verilog(&registry, 12).unwrap(), verilog);
+    }
+
+    #[test]
+    fn generated_names_round_trip() {
+        // Port names which look like the generated nets and instances, or are reserved words
+        let mut builder = TransparentBuilder::default();
+        let y = builder.nand(Signal::Input(0), Signal::Input(1));
+        let pins = Pins { input: vec!["n0_2".into(), "wire".into()], output: vec!["g0".into()] };
+        let mut registry = Registry::default();
+        registry.insert(builder.build(1, "Named".into(), pins, &[y]));
+
+        let verilog = definition_to_verilog(&registry, 1).unwrap();
+        assert!(verilog.starts_with("module Named (n0_2, wire_, g0);"), "{}", verilog);
+
+        let mut imported = Registry::default();
+        import_verilog(&verilog, &imported, 2).unwrap().into_iter().for_each(|x| imported.insert(x));
+        assert_eq!(imported.get_definition(2).unwrap().pins.input, vec!["n0_2", "wire_"]);
+        for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+            assert_eq!(simulate(&imported, 2, a, b), !(a && b), "a = {}, b = {}", a, b);
+        }
+    }
+
+    /// Drives the two inputs of the definition with switches and returns its output.
+    fn simulate(registry: &Registry, def_id: i32, a: bool, b: bool) -> bool {
+        let circuit_def = CircuitDefinition {
+            components: vec![
+                Component { def_id, id: 0 },
+                Component { def_id: SWITCH_ID, id: 1 },
+                Component { def_id: SWITCH_ID, id: 2 },
+                Component { def_id: LED_ID, id: 3 },
+            ],
+            connections: vec![
+                Connection { from: Connector::new(1, 0), to: vec![Connector::new(0, 0)] },
+                Connection { from: Connector::new(2, 0), to: vec![Connector::new(0, 1)] },
+                Connection { from: Connector::new(0, 2), to: vec![Connector::new(3, 0)] },
+            ],
+            ..Default::default()
+        };
+        let mut sim = Simulation {
+            circuit: Circuit::from_definition(registry, circuit_def).unwrap(),
+            ..Default::default()
+        };
+        sim.circuit.components.get_mut(&1).unwrap().as_any_mut().downcast_mut::<Switch>().unwrap().output = a;
+        sim.circuit.components.get_mut(&2).unwrap().as_any_mut().downcast_mut::<Switch>().unwrap().output = b;
+        sim.init();
+        sim.tick_for(32);
+
+        sim.circuit.components[&3].as_any().downcast_ref::<Led>().unwrap().value
+    }
+
+    #[test]
+    fn decomposed_gates_simulate() {
+        let source = "
+            // Gates are decomposed into NAND gates
+            module Xor (input a, input b, output y);
+                xor (y, a, b);
+            endmodule
+        ";
+        let mut registry = Registry::default();
+        import_verilog(source, &registry, 1).unwrap().into_iter().for_each(|x| registry.insert(x));
+
+        for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+            assert_eq!(simulate(&registry, 1, a, b), a ^ b, "a = {}, b = {}", a, b);
+        }
+    }
+
+    #[test]
+    fn tristate_gates_simulate() {
+        let source = "
+            module Bufif0 (input a, input en, output y);
+                bufif0 (y, a, en);
+            endmodule
+            module Notif0 (input a, input en, output y);
+                notif0 (y, a, en);
+            endmodule
+            module Notif1 (input a, input en, output y);
+                notif1 (y, a, en);
+            endmodule
+        ";
+        let mut registry = Registry::default();
+        import_verilog(source, &registry, 1).unwrap().into_iter().for_each(|x| registry.insert(x));
+
+        // Only the enabled rows are defined, disabled outputs are left floating
+        for a in [false, true] {
+            assert_eq!(simulate(&registry, 1, a, false), a, "bufif0: a = {}", a);
+            assert_eq!(simulate(&registry, 2, a, false), !a, "notif0: a = {}", a);
+            assert_eq!(simulate(&registry, 3, a, true), !a, "notif1: a = {}", a);
+        }
+    }
+
+    #[test]
+    fn import_errors() {
+        let registry = Registry::default();
+        let result = import_verilog("module M (a, y);\n    input a;\n    output y;\n    foo u0 (a, y);\nendmodule", &registry, 1);
+        assert!(matches!(result, Err(UnknownModule(x)) if x == "foo"));
+
+        let result = import_verilog("module M (a, y);\n    input a;\n    output y\nendmodule", &registry, 1);
+        assert!(matches!(result, Err(SyntaxError { line: 4, .. })));
+
+        let result = import_verilog("module M (a, y);\n    input a;\n    output y;\nendmodule", &registry, 1);
+        assert!(matches!(result, Err(UndrivenOutput(_, x)) if x == "y"));
+
+        // Registry definitions are not ordered, so a name has to identify a single one
+        let mut registry = load_registry();
+        let mut duplicate = registry.get_definition(1).unwrap().clone();
+        duplicate.id = 7;
+        registry.insert(duplicate);
+        let name = sanitize(&registry.get_definition(1).unwrap().name);
+        let source = format!("module M (a, b, y);\n    input a, b;\n    output y;\n    {} u0 (a, b, y);\nendmodule", name);
+        let result = import_verilog(&source, &registry, 10);
+        assert!(matches!(result, Err(AmbiguousModule(x, ids)) if x == name && ids == vec![1, 7]));
+    }
+}
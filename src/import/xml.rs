@@ -0,0 +1,179 @@
+use rassert_rs::rassert;
+use super::ImportError;
+
+/// A parsed XML element. Comments, processing instructions and declarations are skipped.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|x| x.0 == name).map(|x| x.1.as_str())
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |x| x.name == name)
+    }
+}
+
+/// Parses an XML document, returning its root element.
+pub(crate) fn parse(source: &str) -> Result<Element, ImportError> {
+    let mut reader = Reader { chars: source.chars().collect(), pos: 0, line: 1 };
+    let mut stack: Vec<Element> = vec![Element::default()];
+
+    while reader.pos < reader.chars.len() {
+        if !reader.starts_with("<") {
+            let text = reader.take_until("<");
+            let text = unescape(&text, reader.line)?;
+            stack.last_mut().unwrap().text.push_str(&text);
+            continue;
+        }
+
+        if reader.starts_with("<!--") {
+            reader.skip_past("-->")?;
+        } else if reader.starts_with("<![CDATA[") {
+            reader.pos += 9;
+            let text = reader.take_until("]]>");
+            reader.skip_past("]]>")?;
+            stack.last_mut().unwrap().text.push_str(&text);
+        } else if reader.starts_with("<?") || reader.starts_with("<!") {
+            reader.skip_past(">")?;
+        } else if reader.starts_with("</") {
+            reader.pos += 2;
+            let name = reader.name();
+            reader.skip_whitespace();
+            reader.expect('>')?;
+
+            let element = stack.pop().unwrap();
+            rassert!(!stack.is_empty() && element.name == name, reader.error(format!("Unexpected closing tag '{}'", name)));
+            stack.last_mut().unwrap().children.push(element);
+        } else {
+            reader.pos += 1;
+            let mut element = Element { name: reader.name(), ..Default::default() };
+            rassert!(!element.name.is_empty(), reader.error("Expected an element name"));
+
+            loop {
+                reader.skip_whitespace();
+                if reader.starts_with("/>") {
+                    reader.pos += 2;
+                    stack.last_mut().unwrap().children.push(element);
+                    break;
+                }
+                if reader.starts_with(">") {
+                    reader.pos += 1;
+                    stack.push(element);
+                    break;
+                }
+
+                let name = reader.name();
+                rassert!(!name.is_empty(), reader.error("Expected an attribute name"));
+                reader.skip_whitespace();
+                reader.expect('=')?;
+                reader.skip_whitespace();
+                let quote = reader.next().filter(|x| *x == '"' || *x == '\'').ok_or_else(|| reader.error("Expected a quoted attribute value"))?;
+                let value = reader.take_until(&quote.to_string());
+                reader.expect(quote)?;
+                element.attributes.push((name, unescape(&value, reader.line)?));
+            }
+        }
+    }
+
+    rassert!(stack.len() == 1, reader.error(format!("Element '{}' is never closed", stack.last().unwrap().name)));
+    stack.pop().unwrap().children.into_iter().next().ok_or_else(|| reader.error("Document has no root element"))
+}
+
+fn unescape(text: &str, line: usize) -> Result<String, ImportError> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find(';').ok_or_else(|| ImportError::SyntaxError { line, message: "Unterminated entity".into() })? + start;
+        let entity = &rest[start + 1..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => entity.strip_prefix("#x").map(|x| u32::from_str_radix(x, 16))
+                .or_else(|| entity.strip_prefix('#').map(|x| x.parse()))
+                .and_then(|x| x.ok())
+                .and_then(char::from_u32)
+                .ok_or_else(|| ImportError::SyntaxError { line, message: format!("Unknown entity '&{};'", entity) })?,
+        };
+        result.push(c);
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+struct Reader {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Reader {
+    fn error(&self, message: impl Into<String>) -> ImportError {
+        ImportError::SyntaxError { line: self.line, message: message.into() }
+    }
+
+    fn starts_with(&self, pattern: &str) -> bool {
+        pattern.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.get(self.pos).copied();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        self.pos += 1;
+        c
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ImportError> {
+        match self.next() {
+            Some(x) if x == c => Ok(()),
+            _ => Err(self.error(format!("Expected '{}'", c))),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.chars.get(self.pos), Some(x) if x.is_whitespace()) {
+            self.next();
+        }
+    }
+
+    fn name(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(&c) = self.chars.get(self.pos) {
+            if !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':')) {
+                break;
+            }
+            name.push(c);
+            self.pos += 1;
+        }
+        name
+    }
+
+    fn take_until(&mut self, pattern: &str) -> String {
+        let mut text = String::new();
+        while self.pos < self.chars.len() && !self.starts_with(pattern) {
+            text.push(self.next().unwrap());
+        }
+        text
+    }
+
+    fn skip_past(&mut self, pattern: &str) -> Result<(), ImportError> {
+        self.take_until(pattern);
+        rassert!(self.starts_with(pattern), self.error(format!("Expected '{}'", pattern)));
+        self.pos += pattern.chars().count();
+        Ok(())
+    }
+}
@@ -4,6 +4,10 @@ pub mod sim;
 pub mod wasm;
 pub mod validation;
 pub mod util;
+pub mod project;
+pub mod export;
+pub mod import;
+pub mod synthesis;
 pub use circuit::Circuit;
 pub use component::Component;
 pub use sim::Simulation;
@@ -0,0 +1,71 @@
+use serde_json::{json, Map, Value};
+use super::ProjectError;
+use ProjectError::*;
+
+/// Version of the project format produced by this crate.
+pub const CURRENT_VERSION: u32 = 1;
+
+type Migration = fn(Value) -> Result<Value, ProjectError>;
+
+/// Upgrades a document from the version equal to its index to the next one.
+const MIGRATIONS: &[Migration] = &[
+    migrate_v0_to_v1,
+];
+
+/// Upgrades a project document of any supported version to `CURRENT_VERSION`.
+///
+/// Documents without a version field are considered to be of version 0.
+pub fn migrate(mut document: Value) -> Result<Value, ProjectError> {
+    let object = document.as_object().ok_or(InvalidDocument("Expected an object".into()))?;
+    let mut version = match object.get("version") {
+        Some(version) => version.as_u64().ok_or(InvalidDocument("Expected an unsigned version".into()))? as u32,
+        None => 0,
+    };
+
+    if version > CURRENT_VERSION {
+        return Err(UnsupportedVersion(version));
+    }
+
+    while version < CURRENT_VERSION {
+        document = MIGRATIONS[version as usize](document)?;
+        version += 1;
+        document["version"] = json!(version);
+    }
+
+    Ok(document)
+}
+
+/// Version 0 documents are the unversioned `{ registry, circuits }` pairs, where the registry is
+/// in the same shape as accepted by `set_registry`.
+///
+/// Version 1 stores only the user definitions as a list and adds project metadata.
+fn migrate_v0_to_v1(document: Value) -> Result<Value, ProjectError> {
+    let mut document = match document {
+        Value::Object(document) => document,
+        _ => return Err(InvalidDocument("Expected an object".into())),
+    };
+
+    let registry = document.remove("registry").unwrap_or_else(|| json!({ "components": {} }));
+    let components = match registry.get("components") {
+        Some(Value::Object(components)) => components.clone(),
+        _ => return Err(InvalidDocument("Expected the registry to contain a components map".into())),
+    };
+
+    let mut definitions: Vec<(i64, Value)> = components.into_iter()
+        .map(|(id, def)| match id.parse() {
+            Ok(parsed) => Ok((parsed, def)),
+            Err(_) => Err(InvalidDocument(format!("Invalid definition ID '{}' in the registry", id))),
+        })
+        .collect::<Result<_, _>>()?;
+
+    // Prebuilt definitions (negative IDs) are provided by the crate itself
+    definitions.retain(|(id, _)| *id >= 0);
+    definitions.sort_by_key(|(id, _)| *id);
+
+    let mut upgraded = Map::new();
+    upgraded.insert("metadata".into(), json!({ "name": "", "description": "" }));
+    upgraded.insert("definitions".into(), Value::Array(definitions.into_iter().map(|(_, def)| def).collect()));
+    upgraded.insert("circuits".into(), document.remove("circuits").unwrap_or_else(|| json!([])));
+
+    Ok(Value::Object(upgraded))
+}
@@ -0,0 +1,280 @@
+mod migration;
+mod schema;
+pub use migration::{migrate, CURRENT_VERSION};
+pub use schema::schema;
+
+use std::collections::HashSet;
+use rassert_rs::rassert;
+
+use crate::wasm;
+use crate::circuit::{CircuitDefinition, DefinitionError, Registry};
+use crate::circuit::registry::REGISTRY;
+use crate::component::ComponentDefinition;
+
+/// A saved project containing user definitions, circuits and metadata.
+///
+/// Documents are always upgraded to `CURRENT_VERSION` when loaded.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Project {
+    pub version: u32,
+    pub metadata: ProjectMetadata,
+    pub definitions: Vec<ComponentDefinition>,
+    pub circuits: Vec<CircuitDefinition>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ProjectMetadata {
+    pub name: String,
+    #[serde(rename = "description", default)]
+    pub desc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+impl Project {
+    /// Creates an empty project of the current version.
+    pub fn new(metadata: ProjectMetadata) -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            metadata,
+            definitions: Vec::new(),
+            circuits: Vec::new(),
+        }
+    }
+
+    /// Parses a project document of any supported version.
+    pub fn from_json(json: &str) -> Result<Self, ProjectError> {
+        Self::from_value(serde_json::from_str(json)?)
+    }
+
+    /// Upgrades a project document of any supported version and parses it.
+    pub fn from_value(document: serde_json::Value) -> Result<Self, ProjectError> {
+        Ok(serde_json::from_value(migrate(document)?)?)
+    }
+
+    /// Builds a registry containing the prebuilt and all of the project's definitions.
+    ///
+    /// Definitions must have unique IDs, and may not use the negative IDs of the prebuilt ones.
+    pub fn registry(&self) -> Result<Registry, DefinitionError> {
+        let mut registry = Registry::default();
+        let mut ids = HashSet::new();
+        for def in self.definitions.iter() {
+            rassert!(def.id >= 0, DefinitionError::ReservedDefinitionId(def.id));
+            rassert!(ids.insert(def.id), DefinitionError::DuplicateDefinitionId(def.id));
+            registry.insert(def.clone());
+        }
+
+        // Check only once everything is inserted since definitions can come in any order
+        for def in self.definitions.iter() {
+            if let Some(cycle) = registry.find_cycle(def) {
+                return Err(DefinitionError::RecursiveDefinition(cycle));
+            }
+        }
+
+        Ok(registry)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProjectError {
+    #[error("Project version {0} is newer than the supported version {}.", CURRENT_VERSION)]
+    UnsupportedVersion(u32),
+
+    #[error("Invalid project document. Context: {0}")]
+    InvalidDocument(String),
+
+    #[error("Encountered a format error.")]
+    FormatError(#[from] serde_json::Error),
+}
+
+/// Upgrades the project document, replaces the registry with the project's definitions and
+/// returns the upgraded project.
+#[wasm::wasm_bindgen]
+pub fn load_project(document: wasm::JsValue) -> Result<wasm::JsValue, String> {
+    let document = document.into_serde().expect("Expected the project to be in JSON format.");
+    let project = Project::from_value(document).map_err(|e| e.to_string())?;
+    let registry = project.registry().map_err(|e| e.to_string())?;
+    REGISTRY.with(|reg| reg.lock().replace(registry));
+
+    Ok(wasm::JsValue::from_serde(&project).unwrap())
+}
+
+/// Returns the JSON Schema of the current project version.
+#[wasm::wasm_bindgen]
+pub fn project_schema() -> wasm::JsValue {
+    wasm::JsValue::from_serde(&schema()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Circuit;
+
+    fn legacy_document() -> serde_json::Value {
+        let mut components = serde_json::Map::new();
+        for def in [
+            include_str!("../../tests/assets/ab_inverted_definition.json"),
+            include_str!("../../tests/assets/and_gate_definition.json"),
+            include_str!("../../tests/assets/not_gate_definition.json"),
+        ] {
+            let def: serde_json::Value = serde_json::from_str(def).unwrap();
+            components.insert(def["id"].to_string(), def);
+        }
+        let circuit: serde_json::Value = serde_json::from_str(include_str!("../../tests/assets/ab_inverted_on_not_circuit.json")).unwrap();
+
+        serde_json::json!({
+            "registry": { "components": components },
+            "circuits": [circuit],
+        })
+    }
+
+    #[test]
+    fn legacy_document_is_migrated() {
+        let project = Project::from_value(legacy_document()).unwrap();
+        assert_eq!(project.version, CURRENT_VERSION);
+        assert_eq!(project.definitions.iter().map(|x| x.id).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(project.circuits.len(), 1);
+
+        let registry = project.registry().unwrap();
+        Circuit::from_definition(&registry, project.circuits[0].clone()).unwrap();
+
+        // Current documents round-trip unchanged
+        let serialized = serde_json::to_value(&project).unwrap();
+        assert_eq!(migrate(serialized.clone()).unwrap(), serialized);
+        assert_eq!(Project::from_value(serialized).unwrap(), project);
+    }
+
+    #[test]
+    fn invalid_definition_ids_rejected() {
+        let mut document = legacy_document();
+        document["registry"]["components"]["first"] = document["registry"]["components"]["1"].clone();
+        assert!(matches!(Project::from_value(document), Err(ProjectError::InvalidDocument(_))));
+
+        let mut project = Project::from_value(legacy_document()).unwrap();
+        project.definitions[1].id = 1;
+        assert!(matches!(project.registry(), Err(DefinitionError::DuplicateDefinitionId(1))));
+
+        project.definitions[1].id = crate::circuit::registry::NAND_ID;
+        assert!(matches!(project.registry(), Err(DefinitionError::ReservedDefinitionId(-1))));
+    }
+
+    #[test]
+    fn newer_version_rejected() {
+        let mut document = serde_json::to_value(Project::new(ProjectMetadata::default())).unwrap();
+        document["version"] = serde_json::json!(CURRENT_VERSION + 1);
+
+        let result = Project::from_value(document);
+        assert!(matches!(result, Err(ProjectError::UnsupportedVersion(v)) if v == CURRENT_VERSION + 1));
+    }
+
+    #[test]
+    fn schema_references_resolve() {
+        fn check_refs(value: &serde_json::Value, schema: &serde_json::Value) {
+            match value {
+                serde_json::Value::Object(object) => {
+                    if let Some(reference) = object.get("$ref") {
+                        let pointer = reference.as_str().unwrap().trim_start_matches('#');
+                        assert!(schema.pointer(pointer).is_some(), "Unresolved reference {}", reference);
+                    }
+                    object.values().for_each(|x| check_refs(x, schema));
+                },
+                serde_json::Value::Array(array) => array.iter().for_each(|x| check_refs(x, schema)),
+                _ => {},
+            }
+        }
+
+        let schema = schema();
+        check_refs(&schema, &schema);
+        assert_eq!(schema["properties"]["version"]["const"], CURRENT_VERSION);
+    }
+
+    /// Checks the value against the subset of JSON Schema used by [`schema`]. Properties which
+    /// the schema does not declare are rejected too, so that new fields are not forgotten in it.
+    fn check_schema(value: &serde_json::Value, node: &serde_json::Value, root: &serde_json::Value, path: &str) {
+        use serde_json::Value;
+
+        for (keyword, expected) in node.as_object().unwrap() {
+            match keyword.as_str() {
+                "$schema" | "title" | "$defs" | "additionalProperties" => {},
+                "$ref" => {
+                    let target = root.pointer(expected.as_str().unwrap().trim_start_matches('#')).unwrap();
+                    check_schema(value, target, root, path);
+                },
+                "type" => {
+                    let types: Vec<&str> = match expected {
+                        Value::Array(types) => types.iter().map(|x| x.as_str().unwrap()).collect(),
+                        _ => vec![expected.as_str().unwrap()],
+                    };
+                    let matches = |ty: &str| match ty {
+                        "object" => value.is_object(),
+                        "array" => value.is_array(),
+                        "string" => value.is_string(),
+                        "integer" => value.is_i64() || value.is_u64(),
+                        "boolean" => value.is_boolean(),
+                        "null" => value.is_null(),
+                        _ => panic!("Unsupported type {}", ty),
+                    };
+                    assert!(types.into_iter().any(matches), "{} is not of type {}: {}", path, expected, value);
+                },
+                "const" => assert_eq!(value, expected, "{}", path),
+                "enum" => assert!(expected.as_array().unwrap().contains(value), "{} is not one of {}", path, expected),
+                "minimum" => assert!(value.as_f64().unwrap() >= expected.as_f64().unwrap(), "{} is below {}", path, expected),
+                "maximum" => assert!(value.as_f64().unwrap() <= expected.as_f64().unwrap(), "{} is above {}", path, expected),
+                "required" => for name in expected.as_array().unwrap() {
+                    assert!(value.get(name.as_str().unwrap()).is_some(), "{} is missing {}", path, name);
+                },
+                "items" => for (i, item) in value.as_array().unwrap().iter().enumerate() {
+                    check_schema(item, expected, root, &format!("{}/{}", path, i));
+                },
+                "propertyNames" => {
+                    assert_eq!(expected["pattern"], "^[0-9]+$", "Unsupported property name pattern");
+                    for name in value.as_object().unwrap().keys() {
+                        assert!(name.chars().all(|x| x.is_ascii_digit()) && !name.is_empty(), "{} has property {}", path, name);
+                    }
+                },
+                "properties" => for (name, property) in value.as_object().unwrap() {
+                    let path = format!("{}/{}", path, name);
+                    match (expected.get(name), node.get("additionalProperties")) {
+                        (Some(declared), _) | (None, Some(declared)) => check_schema(property, declared, root, &path),
+                        (None, None) => panic!("{} is not declared in the schema", path),
+                    }
+                },
+                keyword => panic!("Unsupported keyword {}", keyword),
+            }
+        }
+
+        // Objects without declared properties may only have the additional ones
+        if let (Some(object), None, Some(additional)) = (value.as_object(), node.get("properties"), node.get("additionalProperties")) {
+            for (name, property) in object {
+                check_schema(property, additional, root, &format!("{}/{}", path, name));
+            }
+        }
+    }
+
+    #[test]
+    fn serialized_project_conforms_to_schema() {
+        let mut project = Project::new(ProjectMetadata { name: "Fixtures".into(), desc: "All fixtures".into(), author: Some("Test".into()) });
+        for def in [
+            include_str!("../../tests/assets/and_gate_definition.json"),
+            include_str!("../../tests/assets/not_gate_definition.json"),
+            include_str!("../../tests/assets/ab_inverted_definition.json"),
+        ] {
+            project.definitions.push(serde_json::from_str(def).unwrap());
+        }
+        for circuit in [
+            include_str!("../../tests/assets/nand_gate_circuit.json"),
+            include_str!("../../tests/assets/ab_inverted_on_not_circuit.json"),
+        ] {
+            project.circuits.push(serde_json::from_str(circuit).unwrap());
+        }
+
+        // Optional fields which the fixtures leave out
+        project.definitions[0].truth_table.as_mut().unwrap().unstable = vec![1];
+        let delay: crate::circuit::Params = [("delay".to_string(), serde_json::json!(3))].into();
+        project.definitions[0].circuit.as_mut().unwrap().params = Some([(1, delay.clone())].into());
+        project.circuits[0].params = Some([(1, delay)].into());
+
+        let schema = schema();
+        check_schema(&serde_json::to_value(&project).unwrap(), &schema, &schema, "");
+    }
+}
@@ -0,0 +1,156 @@
+use serde_json::{json, Value};
+use super::CURRENT_VERSION;
+
+/// Returns the JSON Schema (draft 2020-12) of the current project version.
+pub fn schema() -> Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Digisim project",
+        "type": "object",
+        "required": ["version", "metadata", "definitions", "circuits"],
+        "properties": {
+            "version": { "const": CURRENT_VERSION },
+            "metadata": { "$ref": "#/$defs/metadata" },
+            "definitions": {
+                "type": "array",
+                "items": { "$ref": "#/$defs/componentDefinition" },
+            },
+            "circuits": {
+                "type": "array",
+                "items": { "$ref": "#/$defs/circuitDefinition" },
+            },
+        },
+        "$defs": {
+            "metadata": {
+                "type": "object",
+                "required": ["name"],
+                "properties": {
+                    "name": { "type": "string" },
+                    "description": { "type": "string" },
+                    "author": { "type": "string" },
+                },
+            },
+            "connector": {
+                "type": "object",
+                "required": ["componentId", "pin"],
+                "properties": {
+                    "componentId": { "$ref": "#/$defs/id" },
+                    "pin": { "$ref": "#/$defs/id" },
+                },
+            },
+            "connection": {
+                "type": "object",
+                "required": ["from", "to"],
+                "properties": {
+                    "from": { "$ref": "#/$defs/connector" },
+                    "to": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/connector" },
+                    },
+                },
+            },
+            "component": {
+                "type": "object",
+                "required": ["definitionId", "id"],
+                "properties": {
+                    "definitionId": { "type": "integer" },
+                    "id": { "$ref": "#/$defs/id" },
+                },
+            },
+            "params": {
+                "type": ["object", "null"],
+                "propertyNames": { "pattern": "^[0-9]+$" },
+                "additionalProperties": { "type": "object" },
+            },
+            "circuit": {
+                "type": "object",
+                "required": ["components", "connections"],
+                "properties": {
+                    "components": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/component" },
+                    },
+                    "connections": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/connection" },
+                    },
+                    "params": { "$ref": "#/$defs/params" },
+                },
+            },
+            "circuitDefinition": {
+                "type": "object",
+                "required": ["id", "name", "description", "components", "connections"],
+                "properties": {
+                    "id": { "type": "integer" },
+                    "name": { "type": "string" },
+                    "description": { "type": "string" },
+                    "components": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/component" },
+                    },
+                    "connections": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/connection" },
+                    },
+                    "params": { "$ref": "#/$defs/params" },
+                },
+            },
+            "pins": {
+                "type": "object",
+                "required": ["input", "output"],
+                "properties": {
+                    "input": { "type": "array", "items": { "type": "string" } },
+                    "output": { "type": "array", "items": { "type": "string" } },
+                },
+            },
+            "pinMapping": {
+                "type": "object",
+                "required": ["input", "output"],
+                "properties": {
+                    "input": { "$ref": "#/$defs/connectorGroups" },
+                    "output": { "$ref": "#/$defs/connectorGroups" },
+                },
+            },
+            "connectorGroups": {
+                "type": "array",
+                "items": {
+                    "type": "array",
+                    "items": { "$ref": "#/$defs/connector" },
+                },
+            },
+            "truthTable": {
+                "type": "object",
+                "required": ["inputs", "outputs"],
+                "properties": {
+                    "inputs": { "$ref": "#/$defs/rows" },
+                    "outputs": { "$ref": "#/$defs/rows" },
+                    "unstable": { "type": "array", "items": { "type": "integer", "minimum": 0 } },
+                },
+            },
+            "rows": {
+                "type": "array",
+                "items": { "type": "array", "items": { "type": "boolean" } },
+            },
+            "componentDefinition": {
+                "type": "object",
+                "required": ["id", "name", "description", "type", "pins"],
+                "properties": {
+                    "id": { "type": "integer" },
+                    "name": { "type": "string" },
+                    "description": { "type": "string" },
+                    "type": { "enum": ["Builtin", "Transparent", "Compiled", "Functional"] },
+                    "pins": { "$ref": "#/$defs/pins" },
+                    "pinMapping": { "$ref": "#/$defs/pinMapping" },
+                    "circuit": { "$ref": "#/$defs/circuit" },
+                    "truthTable": { "$ref": "#/$defs/truthTable" },
+                    "booleanFunction": { "type": "string" },
+                },
+            },
+            "id": {
+                "type": "integer",
+                "minimum": 0,
+                "maximum": u32::MAX,
+            },
+        },
+    })
+}
@@ -9,8 +9,8 @@ pub use wheel::TimingWheel;
 pub use settings::Settings;
 
 use crate::circuit::registry::REGISTRY;
-use crate::circuit::{Circuit, Connector, CircuitState};
-use std::collections::HashSet;
+use crate::circuit::{Circuit, Connector, CircuitState, ComponentPath, InspectionError};
+use std::collections::{HashMap, HashSet};
 use crate::{wasm, log};
 
 /// Simulation context
@@ -23,6 +23,10 @@ pub struct Simulation {
     pub(crate) circuit: Circuit,
     pub(crate) wheel: TimingWheel,
     pub(crate) elapsed: u128,
+
+    /// Last value emitted by each concrete output connector, only recorded once inspection is
+    /// enabled.
+    pub(crate) nets: Option<HashMap<Connector, bool>>,
 }
 
 #[wasm::wasm_bindgen]
@@ -49,8 +53,15 @@ impl Simulation {
         for event in events {
             let component = self.circuit.components.get_mut(&event.src.component).unwrap();
             component.update(event);
+            if let Some(nets) = self.nets.as_mut() {
+                nets.insert(event.src, event.value);
+            }
 
-            for to in self.circuit.connections[&event.src].iter() {
+            // Outputs left unconnected have no connections
+            let Some(connections) = self.circuit.connections.get(&event.src) else {
+                continue;
+            };
+            for to in connections.iter() {
                 let component = self.circuit.components.get_mut(&to.component).unwrap();
                 component.set_pin(to.pin, event);
                 activity_set.insert(to.component);
@@ -99,17 +110,37 @@ impl Simulation {
     pub fn reset(&mut self) {
         self.circuit.components.values_mut().for_each(|x| x.reset());
         self.wheel.reset();
+        if let Some(nets) = self.nets.as_mut() {
+            nets.clear();
+        }
+    }
+
+    /// Starts recording the value on every net, which is required for inspecting them. Nets
+    /// driven before then read as unknown.
+    pub fn enable_inspection(&mut self) {
+        self.nets.get_or_insert_with(Default::default);
     }
 
     /// Returns a JSON object containing the circuit state.
     pub fn circuit_state(&self) -> wasm::JsValue {
-        let mut state = CircuitState::default();
-        for id in self.circuit.output_components.iter() {
-            let component = self.circuit.components.get(id).unwrap();
-            state.data.insert(*id, component.get_state());
-        }
+        self.state().to_wasm_json()
+    }
+
+    /// Returns a JSON array containing the values on the internal connections of the
+    /// transparent component at the given path, e.g. `0/3`. Values which are unknown because the
+    /// connection has not been driven yet are null.
+    pub fn state_of(&self, path: &str) -> Result<wasm::JsValue, String> {
+        let path: ComponentPath = path.parse().map_err(|_| format!("Invalid component path '{}'.", path))?;
+        let state = self.inner_state(&path).map_err(|e| e.to_string())?;
 
-        state.to_wasm_json()
+        let state: Vec<serde_json::Value> = state.into_iter()
+            .map(|(at, value)| serde_json::json!({
+                "connector": at,
+                "value": value,
+            }))
+            .collect();
+
+        Ok(wasm::JsValue::from_serde(&state).unwrap())
     }
 
     pub fn set_circuit(&mut self, circuit: wasm::JsValue) {
@@ -119,6 +150,9 @@ impl Simulation {
             self.circuit = Circuit::from_definition(&reg, circuit_def).unwrap();
         });
         self.wheel.reset();
+        if let Some(nets) = self.nets.as_mut() {
+            nets.clear();
+        }
     }
     
     pub fn set_settings(&mut self, settings: wasm::JsValue) {
@@ -129,18 +163,207 @@ impl Simulation {
 
     pub fn insert_input_event(&mut self, event: wasm::JsValue) -> Result<(), String> {
         let user_event: UserEvent = event.into_serde().unwrap();
-        let component = self.circuit.components.get(&user_event.component_id).unwrap();
+        self.process_user_event(user_event).map_err(|e| e.to_string())
+    }
+}
 
-        let events = match component.process_user_event(user_event) {
-            Ok(events) => events,
-            Err(e) => return Err(e.to_string()),
-        };
+impl Simulation {
+    /// Creates a simulation of an already built circuit.
+    pub fn with_circuit(settings: Settings, circuit: Circuit) -> Self {
+        Self {
+            circuit,
+            ..Self::new(settings)
+        }
+    }
 
-        for event in events {
-            self.wheel.schedule(component.delay(), event);
+    pub fn circuit(&self) -> &Circuit {
+        &self.circuit
+    }
+
+    /// Returns the number of ticks since the simulation was created.
+    pub fn elapsed(&self) -> u128 {
+        self.elapsed
+    }
+
+    /// Returns the state of the output components, keyed by their IDs.
+    pub fn state(&self) -> CircuitState {
+        let mut state = CircuitState::default();
+        for id in self.circuit.output_components.iter() {
+            let component = self.circuit.components.get(id).unwrap();
+            state.data.insert(*id, component.get_state());
+        }
+
+        state
+    }
+
+    /// Schedules the events with which the component responds to the user event, e.g. toggling
+    /// a switch or pulsing a button.
+    pub fn process_user_event(&mut self, user_event: UserEvent) -> Result<(), UserEventError> {
+        let component = self.circuit.components.get(&user_event.component_id)
+            .ok_or(UserEventError::UnknownComponent(user_event.component_id))?;
+        let events = component.process_user_event(user_event)?;
+
+        // Later events would wrap around the timing wheel and fire too early
+        let max_delay = self.settings.max_delay;
+        if let Some(&(delay, _)) = events.iter().find(|(delay, _)| *delay >= max_delay) {
+            return Err(UserEventError::ExceedsMaxDelay { delay, max_delay });
+        }
+
+        for (delay, event) in events {
+            self.wheel.schedule(delay, event);
         }
 
         Ok(())
     }
+
+    /// Returns the value on the output pin of the component at the given path, which is driven
+    /// by the concrete components it reroutes to, or `None` if none of them has driven it yet.
+    pub fn value_at(&self, path: &ComponentPath, pin: u32) -> Result<Option<bool>, InspectionError> {
+        let id = self.circuit.hierarchy.id_of(path).ok_or_else(|| InspectionError::UnknownPath(path.clone()))?;
+        let drivers = self.circuit.reroute_to_concrete(Connector::new(id, pin)).map_err(|_| InspectionError::InvalidPin(path.clone(), pin))?;
+
+        self.driven_value(&drivers)
+    }
+
+    /// Returns whether no more events are scheduled, i.e. the circuit has settled.
+    pub fn is_settled(&self) -> bool {
+        self.wheel.is_empty()
+    }
+
+    /// Returns the values on the internal connections of the transparent component at the
+    /// given path, keyed by the connectors local to its definition.
+    pub fn inner_state(&self, path: &ComponentPath) -> Result<Vec<(Connector, Option<bool>)>, InspectionError> {
+        self.circuit.inner_nets(path)?
+            .into_iter()
+            .map(|(at, drivers)| Ok((at, self.driven_value(&drivers)?)))
+            .collect()
+    }
+
+    /// Returns the value on a connection with the given drivers, which is high if any of them
+    /// is and unknown if none of them has emitted anything yet.
+    fn driven_value(&self, drivers: &[Connector]) -> Result<Option<bool>, InspectionError> {
+        let nets = self.nets.as_ref().ok_or(InspectionError::NotEnabled)?;
+        Ok(drivers.iter().filter_map(|x| nets.get(x).copied()).reduce(|a, b| a || b))
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{CircuitDefinition, Connection, Registry};
+    use crate::circuit::registry::{BUTTON_ID, LED_ID, SWITCH_ID};
+    use crate::component::{ComponentDefinition, Led, Switch};
+    use crate::component::definition::Component;
+
+    #[test]
+    fn inner_state_of_nested_components() {
+        let mut registry = Registry::default();
+        for def in [
+            include_str!("../../tests/assets/and_gate_definition.json"),
+            include_str!("../../tests/assets/not_gate_definition.json"),
+            include_str!("../../tests/assets/ab_inverted_definition.json"),
+        ] {
+            let parsed: ComponentDefinition = serde_json::from_str(def).unwrap();
+            registry.insert(parsed);
+        }
+
+        // Drive the AB inverted component with A = 0, B = 1
+        let circuit_def = CircuitDefinition {
+            components: vec![
+                Component { def_id: 3, id: 0 },
+                Component { def_id: SWITCH_ID, id: 10 },
+                Component { def_id: SWITCH_ID, id: 11 },
+            ],
+            connections: vec![
+                Connection { from: Connector::new(10, 0), to: vec![Connector::new(0, 0)] },
+                Connection { from: Connector::new(11, 0), to: vec![Connector::new(0, 1)] },
+            ],
+            ..Default::default()
+        };
+        let mut sim = Simulation {
+            circuit: Circuit::from_definition(&registry, circuit_def).unwrap(),
+            ..Default::default()
+        };
+        sim.circuit.components.get_mut(&11).unwrap().as_any_mut().downcast_mut::<Switch>().unwrap().output = true;
+        assert!(matches!(sim.inner_state(&"0".parse().unwrap()), Err(InspectionError::NotEnabled)));
+
+        sim.enable_inspection();
+        sim.init();
+        assert_eq!(sim.value_at(&"0".parse().unwrap(), 2).unwrap(), None);
+        sim.tick_for(16);
+
+        let state = sim.inner_state(&"0".parse().unwrap()).unwrap();
+        assert_eq!(state, vec![(Connector::new(0, 1), Some(true)), (Connector::new(1, 2), Some(true))]);
+
+        let state = sim.inner_state(&"0/1".parse().unwrap()).unwrap();
+        assert_eq!(state, vec![(Connector::new(0, 2), Some(false)), (Connector::new(1, 2), Some(true))]);
+
+        assert!(matches!(sim.inner_state(&"0/1/0".parse().unwrap()), Err(InspectionError::NotTransparent(_))));
+        assert!(matches!(sim.inner_state(&"5".parse().unwrap()), Err(InspectionError::UnknownPath(_))));
+
+        assert_eq!(sim.value_at(&"0".parse().unwrap(), 2).unwrap(), Some(true));
+        assert_eq!(sim.value_at(&"0/1/0".parse().unwrap(), 2).unwrap(), Some(false));
+        assert!(matches!(sim.value_at(&"0".parse().unwrap(), 7), Err(InspectionError::InvalidPin(_, 7))));
+
+        // Toggle to A = 1, B = 0
+        for id in [10, 11] {
+            sim.process_user_event(UserEvent { component_id: id, payload: "toggle".into() }).unwrap();
+        }
+        sim.tick_for(16);
+        assert_eq!(sim.value_at(&"0".parse().unwrap(), 2).unwrap(), Some(false));
+        assert!(matches!(
+            sim.process_user_event(UserEvent { component_id: 99, payload: "toggle".into() }),
+            Err(UserEventError::UnknownComponent(99)),
+        ));
+    }
+
+    #[test]
+    fn button_pulse() {
+        // Button 1 with a pulse width of 4 lighting up the LED 2
+        let circuit_def = CircuitDefinition {
+            components: vec![
+                Component { def_id: BUTTON_ID, id: 1 },
+                Component { def_id: LED_ID, id: 2 },
+            ],
+            connections: vec![
+                Connection { from: Connector::new(1, 0), to: vec![Connector::new(2, 0)] },
+            ],
+            params: Some(HashMap::from([(1, HashMap::from([("pulseWidth".into(), 4.into())]))])),
+            ..Default::default()
+        };
+        let mut sim = Simulation::with_circuit(Settings::new(8), Circuit::from_definition(&Registry::default(), circuit_def).unwrap());
+        sim.init();
+        sim.tick();
+
+        let led = |sim: &Simulation| sim.circuit.components[&2].as_any().downcast_ref::<Led>().unwrap().value();
+        let event = |payload: &str| UserEvent { component_id: 1, payload: payload.into() };
+
+        sim.process_user_event(event("press")).unwrap();
+        sim.tick_for(2);
+        assert!(led(&sim));
+        sim.tick_for(8);
+        assert!(led(&sim));
+        sim.process_user_event(event("release")).unwrap();
+        sim.tick_for(2);
+        assert!(!led(&sim));
+
+        // The pulse goes high after the delay of the button and releases itself 4 ticks later
+        sim.process_user_event(event("pulse")).unwrap();
+        let lit: Vec<bool> = (0..8).map(|_| { sim.tick(); led(&sim) }).collect();
+        assert_eq!(lit, [false, true, true, true, true, false, false, false]);
+        assert!(sim.is_settled());
+
+        assert!(matches!(sim.process_user_event(event("toggle")), Err(UserEventError::InvalidPayload(_))));
+
+        // A release past the end of the timing wheel would come around too early
+        sim.circuit = Circuit::from_definition(&Registry::default(), CircuitDefinition {
+            components: vec![Component { def_id: BUTTON_ID, id: 1 }],
+            params: Some(HashMap::from([(1, HashMap::from([("pulseWidth".into(), 7.into())]))])),
+            ..Default::default()
+        }).unwrap();
+        assert!(matches!(
+            sim.process_user_event(event("pulse")),
+            Err(UserEventError::ExceedsMaxDelay { delay: 8, max_delay: 8 }),
+        ));
+    }
+}
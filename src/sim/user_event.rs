@@ -4,6 +4,12 @@ use crate::circuit::Id;
 pub enum UserEventError {
 	#[error("Invalid payload received. Context: {0}")]
 	InvalidPayload(String),
+
+	#[error("No component with id {0} exists.")]
+	UnknownComponent(Id),
+
+	#[error("Cannot schedule an event {delay} ticks ahead, the maximum delay is {max_delay}.")]
+	ExceedsMaxDelay { delay: u32, max_delay: u32 },
 }
 
 #[derive(serde::Deserialize)]
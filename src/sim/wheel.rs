@@ -43,6 +43,11 @@ impl TimingWheel {
         self.wheel.resize(max_delay as usize, Default::default());
     }
 
+    /// Returns whether no events are scheduled.
+    pub fn is_empty(&self) -> bool {
+        self.wheel.iter().all(|x| x.is_empty())
+    }
+
     pub fn reset(&mut self) {
         self.wheel.iter_mut().for_each(|x| x.clear());
     }
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use rassert_rs::rassert;
+
+use super::SynthesisError;
+
+/// Reference to a node of a [`Bdd`].
+pub(crate) type NodeRef = u32;
+
+pub(crate) const FALSE: NodeRef = 0;
+pub(crate) const TRUE: NodeRef = 1;
+
+/// Variable of the terminal nodes, ordered after all the other variables.
+const TERMINAL: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Op {
+    And,
+    Xor,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    var: u32,
+    low: NodeRef,
+    high: NodeRef,
+}
+
+/// A reduced ordered binary decision diagram, ordering the variables by their index.
+#[derive(Debug)]
+pub(crate) struct Bdd {
+    nodes: Vec<Node>,
+    unique: HashMap<(u32, NodeRef, NodeRef), NodeRef>,
+    cache: HashMap<(Op, NodeRef, NodeRef), NodeRef>,
+    max_nodes: usize,
+}
+
+impl Bdd {
+    /// Creates an empty diagram, which fails with `TooComplex` once it grows past `max_nodes`.
+    pub fn new(max_nodes: usize) -> Self {
+        let terminal = |value| Node { var: TERMINAL, low: value, high: value };
+        Self {
+            nodes: vec![terminal(FALSE), terminal(TRUE)],
+            unique: HashMap::new(),
+            cache: HashMap::new(),
+            max_nodes,
+        }
+    }
+
+    pub fn variable(&mut self, var: u32) -> Result<NodeRef, SynthesisError> {
+        self.node(var, FALSE, TRUE)
+    }
+
+    pub fn and(&mut self, a: NodeRef, b: NodeRef) -> Result<NodeRef, SynthesisError> {
+        self.apply(Op::And, a, b)
+    }
+
+    pub fn xor(&mut self, a: NodeRef, b: NodeRef) -> Result<NodeRef, SynthesisError> {
+        self.apply(Op::Xor, a, b)
+    }
+
+    pub fn nand(&mut self, a: NodeRef, b: NodeRef) -> Result<NodeRef, SynthesisError> {
+        let and = self.and(a, b)?;
+        self.xor(and, TRUE)
+    }
+
+    /// Finds an assignment of `num_vars` variables for which the function is true, preferring
+    /// false values.
+    pub fn satisfy(&self, mut node: NodeRef, num_vars: usize) -> Option<Vec<bool>> {
+        if node == FALSE {
+            return None;
+        }
+
+        // Every non-terminal node of a reduced diagram has a path to the true terminal
+        let mut assignment = vec![false; num_vars];
+        while node != TRUE {
+            let Node { var, low, high } = self.nodes[node as usize];
+            if low == FALSE {
+                assignment[var as usize] = true;
+                node = high;
+            } else {
+                node = low;
+            }
+        }
+
+        Some(assignment)
+    }
+
+    fn node(&mut self, var: u32, low: NodeRef, high: NodeRef) -> Result<NodeRef, SynthesisError> {
+        if low == high {
+            return Ok(low);
+        }
+        if let Some(&node) = self.unique.get(&(var, low, high)) {
+            return Ok(node);
+        }
+
+        rassert!(self.nodes.len() < self.max_nodes, SynthesisError::TooComplex);
+        let node = self.nodes.len() as NodeRef;
+        self.nodes.push(Node { var, low, high });
+        self.unique.insert((var, low, high), node);
+        Ok(node)
+    }
+
+    fn apply(&mut self, op: Op, a: NodeRef, b: NodeRef) -> Result<NodeRef, SynthesisError> {
+        // Both operations are commutative
+        let (a, b) = (a.min(b), a.max(b));
+        match op {
+            Op::And if a == FALSE => return Ok(FALSE),
+            Op::And if a == TRUE || a == b => return Ok(b),
+            Op::Xor if a == b => return Ok(FALSE),
+            Op::Xor if a == FALSE => return Ok(b),
+            _ => {},
+        }
+        if let Some(&node) = self.cache.get(&(op, a, b)) {
+            return Ok(node);
+        }
+
+        let (node_a, node_b) = (self.nodes[a as usize], self.nodes[b as usize]);
+        let var = node_a.var.min(node_b.var);
+        let cofactors = |node: Node, x: NodeRef| if node.var == var { (node.low, node.high) } else { (x, x) };
+        let (a_low, a_high) = cofactors(node_a, a);
+        let (b_low, b_high) = cofactors(node_b, b);
+
+        let low = self.apply(op, a_low, b_low)?;
+        let high = self.apply(op, a_high, b_high)?;
+        let node = self.node(var, low, high)?;
+        self.cache.insert((op, a, b), node);
+        Ok(node)
+    }
+}
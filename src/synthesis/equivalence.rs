@@ -0,0 +1,68 @@
+use rassert_rs::rassert;
+
+use crate::circuit::Registry;
+use crate::component::ComponentDefinition;
+use super::SynthesisError;
+use super::bdd::{Bdd, NodeRef, FALSE, TRUE};
+use super::netlist::{Gate, Netlist};
+use SynthesisError::*;
+
+/// The number of BDD nodes after which the equivalence check gives up.
+pub const MAX_BDD_NODES: usize = 1 << 22;
+
+/// An input for which two definitions compute different outputs.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Counterexample {
+    pub input: Vec<bool>,
+    /// Outputs of the reference definition.
+    pub expected: Vec<bool>,
+    /// Outputs of the candidate definition.
+    pub actual: Vec<bool>,
+}
+
+/// Proves that two combinational transparent definitions compute the same function, by
+/// comparing the binary decision diagrams of their outputs.
+///
+/// The definitions must have the same number of inputs and outputs, which are matched by their
+/// position.
+///
+/// # Returns
+/// `None` if the definitions are equivalent, otherwise an input for which their outputs differ.
+pub fn check_equivalence(registry: &Registry, reference: &ComponentDefinition, candidate: &ComponentDefinition) -> Result<Option<Counterexample>, SynthesisError> {
+    rassert!(reference.pins.input.len() == candidate.pins.input.len(), InterfaceMismatch);
+    rassert!(reference.pins.output.len() == candidate.pins.output.len(), InterfaceMismatch);
+
+    let reference = Netlist::trace(registry, reference)?;
+    let candidate = Netlist::trace(registry, candidate)?;
+
+    let mut bdd = Bdd::new(MAX_BDD_NODES);
+    let expected = build(&mut bdd, &reference)?;
+    let actual = build(&mut bdd, &candidate)?;
+    for (&expected, &actual) in expected.iter().zip(actual.iter()) {
+        let difference = bdd.xor(expected, actual)?;
+        if let Some(input) = bdd.satisfy(difference, reference.num_inputs) {
+            return Ok(Some(Counterexample {
+                expected: reference.evaluate(&input),
+                actual: candidate.evaluate(&input),
+                input,
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Builds the diagrams of the netlist's outputs.
+fn build(bdd: &mut Bdd, netlist: &Netlist) -> Result<Vec<NodeRef>, SynthesisError> {
+    let mut nodes = Vec::with_capacity(netlist.gates.len());
+    for gate in netlist.gates.iter() {
+        let node = match *gate {
+            Gate::Input(x) => bdd.variable(x as u32)?,
+            Gate::Constant(value) => if value { TRUE } else { FALSE },
+            Gate::Nand(a, b) => bdd.nand(nodes[a], nodes[b])?,
+        };
+        nodes.push(node);
+    }
+
+    Ok(netlist.outputs.iter().map(|&x| nodes[x]).collect())
+}
@@ -0,0 +1,91 @@
+use rassert_rs::rassert;
+use rustlogic::LogicNode;
+
+use crate::circuit::Registry;
+use crate::component::ComponentDefinition;
+use super::{minimize, Implicant, SynthesisError, MAX_INPUTS};
+use super::netlist::Netlist;
+use SynthesisError::*;
+
+/// The boolean function of a single output pin.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Expression {
+    /// Name of the output pin.
+    pub output: String,
+    /// The minimized function as a sum of products.
+    #[serde(skip)]
+    pub node: LogicNode,
+    /// The minimized function in the syntax of the `booleanFunction` field, e.g. `([A]&~[B])|[C]`.
+    pub expr: String,
+}
+
+/// Extracts the boolean function of every output of a stateless transparent definition.
+///
+/// The definition is flattened and its outputs are traced back through the connections to the
+/// input pins. Definitions containing feedback loops or components other than NAND gates and
+/// constants are rejected, and unconnected inputs of NAND gates read as 0.
+pub fn extract_expressions(registry: &Registry, def: &ComponentDefinition) -> Result<Vec<Expression>, SynthesisError> {
+    let num_inputs = def.pins.input.len();
+    rassert!(num_inputs <= MAX_INPUTS, TooManyInputs(num_inputs));
+    let netlist = Netlist::trace(registry, def)?;
+
+    let mut on: Vec<Vec<u32>> = vec![Vec::new(); netlist.outputs.len()];
+    let mut values = vec![false; netlist.gates.len()];
+    for minterm in 0..1u32 << num_inputs {
+        netlist.evaluate_into(|i| minterm & (1 << (num_inputs - 1 - i)) != 0, &mut values);
+        for (on, &output) in on.iter_mut().zip(netlist.outputs.iter()) {
+            if values[output] {
+                on.push(minterm);
+            }
+        }
+    }
+
+    Ok(def.pins.output.iter()
+        .zip(on.iter())
+        .map(|(output, on)| {
+            let implicants = minimize(num_inputs, on, &[]);
+            Expression {
+                output: output.clone(),
+                node: to_node(&implicants, &def.pins.input),
+                expr: to_expr(&implicants, &def.pins.input),
+            }
+        })
+        .collect())
+}
+
+fn to_node(implicants: &[Implicant], inputs: &[String]) -> LogicNode {
+    implicants.iter()
+        .map(|implicant| {
+            implicant.literals(inputs.len()).into_iter()
+                .map(|(i, positive)| {
+                    let variable = LogicNode::Variable(inputs[i].clone());
+                    if positive { variable } else { LogicNode::Not(Box::new(variable)) }
+                })
+                .reduce(|acc, x| LogicNode::And(Box::new(acc), Box::new(x)))
+                .unwrap_or(LogicNode::True)
+        })
+        .reduce(|acc, x| LogicNode::Or(Box::new(acc), Box::new(x)))
+        .unwrap_or(LogicNode::False)
+}
+
+fn to_expr(implicants: &[Implicant], inputs: &[String]) -> String {
+    if implicants.is_empty() {
+        return "0".into();
+    }
+
+    implicants.iter()
+        .map(|implicant| {
+            let literals: Vec<String> = implicant.literals(inputs.len()).into_iter()
+                .map(|(i, positive)| format!("{}[{}]", if positive { "" } else { "~" }, inputs[i]))
+                .collect();
+            match literals.len() {
+                0 => "1".into(),
+                1 => literals[0].clone(),
+                _ if implicants.len() == 1 => literals.join("&"),
+                _ => format!("({})", literals.join("&")),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("|")
+}
@@ -0,0 +1,139 @@
+use std::collections::{BTreeSet, HashSet};
+
+/// A product term over the inputs, where input `i` corresponds to bit `num_inputs - 1 - i`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Implicant {
+    /// Values of the bits the term depends on, bits outside the term are zero.
+    pub value: u32,
+    /// Bits the term does not depend on.
+    pub mask: u32,
+}
+
+impl Implicant {
+    pub fn covers(&self, minterm: u32) -> bool {
+        minterm & !self.mask == self.value
+    }
+
+    /// Returns the term's literals as input indices and whether they are not complemented.
+    pub fn literals(&self, num_inputs: usize) -> Vec<(usize, bool)> {
+        (0..num_inputs)
+            .filter_map(|i| {
+                let bit = 1 << (num_inputs - 1 - i);
+                (self.mask & bit == 0).then_some((i, self.value & bit != 0))
+            })
+            .collect()
+    }
+}
+
+/// Minimizes a boolean function into a sum of products using the Quine–McCluskey method.
+///
+/// Prime implicants are selected by taking the essential ones first and then searching for the
+/// cover with the fewest terms and literals. The search is bounded, falling back to the best
+/// cover found so far, starting from a greedy one.
+///
+/// # Returns
+/// The selected implicants, sorted. An empty list is the constant 0.
+pub fn minimize(num_inputs: usize, on: &[u32], dont_care: &[u32]) -> Vec<Implicant> {
+    let mut current: BTreeSet<Implicant> = on.iter().chain(dont_care.iter())
+        .map(|&value| Implicant { value, mask: 0 })
+        .collect();
+    let mut primes = BTreeSet::new();
+
+    // Combine implicants differing in a single bit until no more can be combined
+    while !current.is_empty() {
+        let mut combined = BTreeSet::new();
+        let mut used = HashSet::new();
+        for implicant in current.iter() {
+            for bit in (0..num_inputs).map(|x| 1u32 << x) {
+                if implicant.mask & bit != 0 || implicant.value & bit != 0 {
+                    continue;
+                }
+
+                let other = Implicant { value: implicant.value | bit, mask: implicant.mask };
+                if current.contains(&other) {
+                    combined.insert(Implicant { value: implicant.value, mask: implicant.mask | bit });
+                    used.insert(*implicant);
+                    used.insert(other);
+                }
+            }
+        }
+
+        primes.extend(current.into_iter().filter(|x| !used.contains(x)));
+        current = combined;
+    }
+
+    // Cover the minterms, ignoring the don't cares
+    let primes: Vec<Implicant> = primes.into_iter().collect();
+    let mut uncovered: BTreeSet<u32> = on.iter().copied().collect();
+    let mut selected = BTreeSet::new();
+    for &minterm in on.iter() {
+        let mut covering = primes.iter().filter(|x| x.covers(minterm));
+        if let (Some(&prime), None) = (covering.next(), covering.next()) {
+            selected.insert(prime);
+        }
+    }
+    uncovered.retain(|&x| !selected.iter().any(|prime| prime.covers(x)));
+
+    // Cover the remaining minterms greedily, then search for a smaller cover
+    let remaining: Vec<u32> = uncovered.into_iter().collect();
+    let mut greedy = Vec::new();
+    let mut uncovered = remaining.clone();
+    while !uncovered.is_empty() {
+        let &best = primes.iter()
+            .max_by_key(|prime| {
+                let covered = uncovered.iter().filter(|&&x| prime.covers(x)).count();
+                (covered, prime.mask.count_ones(), std::cmp::Reverse(**prime))
+            })
+            .unwrap();
+        uncovered.retain(|&x| !best.covers(x));
+        greedy.push(best);
+    }
+
+    let mut search = CoverSearch { primes: &primes, num_inputs, best: greedy, budget: SEARCH_BUDGET };
+    search.search(&remaining, &mut Vec::new());
+    selected.extend(search.best);
+
+    selected.into_iter().collect()
+}
+
+/// The number of search steps after which the best cover found so far is used.
+const SEARCH_BUDGET: usize = 100_000;
+
+struct CoverSearch<'a> {
+    primes: &'a [Implicant],
+    num_inputs: usize,
+    best: Vec<Implicant>,
+    budget: usize,
+}
+
+impl CoverSearch<'_> {
+    fn cost(&self, cover: &[Implicant]) -> (usize, usize) {
+        let literals = cover.iter().map(|x| self.num_inputs - x.mask.count_ones() as usize).sum();
+        (cover.len(), literals)
+    }
+
+    fn search(&mut self, uncovered: &[u32], selected: &mut Vec<Implicant>) {
+        if self.budget == 0 {
+            return;
+        }
+        self.budget -= 1;
+
+        // Branch on the minterm covered by the fewest primes
+        let Some(&minterm) = uncovered.iter().min_by_key(|&&x| self.primes.iter().filter(|prime| prime.covers(x)).count()) else {
+            if self.cost(selected) < self.cost(&self.best) {
+                self.best = selected.clone();
+            }
+            return;
+        };
+        if selected.len() + 1 > self.best.len() {
+            return;
+        }
+
+        for &prime in self.primes.iter().filter(|x| x.covers(minterm)) {
+            let remaining: Vec<u32> = uncovered.iter().copied().filter(|&x| !prime.covers(x)).collect();
+            selected.push(prime);
+            self.search(&remaining, selected);
+            selected.pop();
+        }
+    }
+}
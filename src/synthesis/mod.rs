@@ -0,0 +1,401 @@
+mod minimize;
+mod netlist;
+mod extract;
+mod bdd;
+mod equivalence;
+pub use minimize::{minimize, Implicant};
+pub use extract::{extract_expressions, Expression};
+pub use equivalence::{check_equivalence, Counterexample, MAX_BDD_NODES};
+
+use std::collections::HashMap;
+use rassert_rs::rassert;
+use rustlogic::LogicNode;
+
+use crate::wasm;
+use crate::circuit::DefinitionError;
+use crate::circuit::registry::REGISTRY;
+use crate::component::ComponentDefinition;
+use crate::component::definition::{Pins, Signal, TransparentBuilder, TruthTable};
+use SynthesisError::*;
+
+/// The largest number of inputs a function is synthesized for.
+pub const MAX_INPUTS: usize = 16;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SynthesisError {
+    #[error("Definition has neither a truth table nor a boolean function.")]
+    NothingToSynthesize,
+
+    #[error("Cannot synthesize a component without inputs.")]
+    NoInputs,
+
+    #[error("Cannot synthesize a component with {0} inputs, at most {MAX_INPUTS} are supported.")]
+    TooManyInputs(usize),
+
+    #[error("Row {0} of the truth table does not match the component's pins.")]
+    InvalidRow(usize),
+
+    #[error("Rows {0} and {1} of the truth table have the same inputs but different outputs.")]
+    ConflictingRows(usize, usize),
+
+    #[error("Failed to parse the boolean function at position {0}.")]
+    InvalidExpression(usize),
+
+    #[error("Variable '{0}' of the boolean function is not an input pin.")]
+    UnknownVariable(String),
+
+    #[error("Expected {expected} boolean functions, one for each output, but got {actual}.")]
+    OutputMismatch { expected: usize, actual: usize },
+
+    #[error("Only transparent definitions can be analyzed.")]
+    NotTransparent,
+
+    #[error("Component {0} is part of a feedback loop, so the definition is not combinational.")]
+    NotCombinational(String),
+
+    #[error("Component {0} is neither a NAND gate nor a constant.")]
+    UnsupportedComponent(String),
+
+    #[error("An input of component {0} is driven by multiple outputs.")]
+    MultipleDrivers(String),
+
+    #[error("Definitions have a different number of inputs or outputs.")]
+    InterfaceMismatch,
+
+    #[error("The definitions are too complex to compare, the decision diagram exceeds {MAX_BDD_NODES} nodes.")]
+    TooComplex,
+
+    #[error("Failed to flatten the definition.")]
+    DefinitionError(#[from] DefinitionError),
+}
+
+/// A single output function, as its minterms and don't care terms.
+struct Function {
+    on: Vec<u32>,
+    dont_care: Vec<u32>,
+}
+
+/// Synthesizes a NAND-only transparent definition from the definition's truth table, or from its
+/// boolean function if it has no truth table.
+///
+/// The boolean function is parsed with `rustlogic`, referencing the input pins as `[A]`.
+pub fn synthesize(def: &ComponentDefinition) -> Result<ComponentDefinition, SynthesisError> {
+    let mut synthesized = if let Some(truth_table) = def.truth_table.as_ref() {
+        from_truth_table(def.id, def.name.clone(), def.pins.clone(), truth_table)?
+    } else if let Some(expr) = def.parsed_expr.clone().map(Ok).or_else(|| def.expr.as_deref().map(rustlogic::parse)) {
+        let expr = expr.map_err(InvalidExpression)?;
+        from_expressions(def.id, def.name.clone(), def.pins.clone(), &[expr])?
+    } else {
+        return Err(NothingToSynthesize);
+    };
+
+    synthesized.desc = def.desc.clone();
+    Ok(synthesized)
+}
+
+/// Synthesizes a NAND-only transparent definition implementing the truth table.
+///
+/// Input combinations missing from the truth table are treated as don't cares.
+pub fn from_truth_table(id: i32, name: String, pins: Pins, truth_table: &TruthTable) -> Result<ComponentDefinition, SynthesisError> {
+    let num_inputs = check_inputs(&pins)?;
+
+    let mut rows: HashMap<u32, usize> = HashMap::new();
+    let mut functions: Vec<Function> = pins.output.iter().map(|_| Function { on: Vec::new(), dont_care: Vec::new() }).collect();
+    for (i, (inputs, outputs)) in truth_table.iter().enumerate() {
+        rassert!(inputs.len() == num_inputs && outputs.len() == pins.output.len(), InvalidRow(i));
+
+        let minterm = minterm(inputs);
+        if let Some(&other) = rows.get(&minterm) {
+            rassert!(truth_table.outputs[other] == *outputs, ConflictingRows(other, i));
+            continue;
+        }
+        rows.insert(minterm, i);
+
+        for (function, &output) in functions.iter_mut().zip(outputs.iter()) {
+            if output {
+                function.on.push(minterm);
+            }
+        }
+    }
+
+    let dont_care: Vec<u32> = (0..1u32 << num_inputs).filter(|x| !rows.contains_key(x)).collect();
+    functions.iter_mut().for_each(|x| x.dont_care = dont_care.clone());
+
+    Ok(realize(id, name, pins, &functions))
+}
+
+/// Synthesizes a NAND-only transparent definition implementing one expression per output.
+///
+/// Variables of the expressions reference the input pins by name.
+pub fn from_expressions(id: i32, name: String, pins: Pins, expressions: &[LogicNode]) -> Result<ComponentDefinition, SynthesisError> {
+    let num_inputs = check_inputs(&pins)?;
+    rassert!(expressions.len() == pins.output.len(), OutputMismatch { expected: pins.output.len(), actual: expressions.len() });
+    for variable in expressions.iter().flat_map(|x| x.get_variables()) {
+        rassert!(pins.input.contains(&variable), UnknownVariable(variable));
+    }
+
+    let mut functions: Vec<Function> = expressions.iter().map(|_| Function { on: Vec::new(), dont_care: Vec::new() }).collect();
+    for minterm in 0..1u32 << num_inputs {
+        let variables: HashMap<&str, bool> = pins.input.iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), minterm & (1 << (num_inputs - 1 - i)) != 0))
+            .collect();
+
+        for (function, expr) in functions.iter_mut().zip(expressions.iter()) {
+            if expr.get_value_from_variables(&variables).map_err(UnknownVariable)? {
+                function.on.push(minterm);
+            }
+        }
+    }
+
+    Ok(realize(id, name, pins, &functions))
+}
+
+fn check_inputs(pins: &Pins) -> Result<usize, SynthesisError> {
+    let num_inputs = pins.input.len();
+    rassert!(num_inputs > 0, NoInputs);
+    rassert!(num_inputs <= MAX_INPUTS, TooManyInputs(num_inputs));
+    Ok(num_inputs)
+}
+
+/// Returns the minterm of the input values, with the first input as the most significant bit.
+fn minterm(inputs: &[bool]) -> u32 {
+    inputs.iter().fold(0, |acc, &x| (acc << 1) | x as u32)
+}
+
+/// Builds the minimized functions as two-level NAND logic, sharing common product terms.
+fn realize(id: i32, name: String, pins: Pins, functions: &[Function]) -> ComponentDefinition {
+    let num_inputs = pins.input.len();
+    let mut builder = TransparentBuilder::default();
+    let mut terms: HashMap<Implicant, Signal> = HashMap::new();
+
+    let outputs: Vec<Signal> = functions.iter()
+        .map(|function| {
+            let implicants = minimize(num_inputs, &function.on, &function.dont_care);
+
+            // Constants are derived from the first input, since only NAND gates are allowed
+            match implicants.as_slice() {
+                [] => {
+                    let input = Signal::Input(0);
+                    let inverted = builder.not(input);
+                    let one = builder.nand(input, inverted);
+                    return builder.not(one);
+                },
+                [implicant] if implicant.mask.count_ones() as usize == num_inputs => {
+                    let input = Signal::Input(0);
+                    let inverted = builder.not(input);
+                    return builder.nand(input, inverted);
+                },
+                _ => {},
+            }
+
+            let products: Vec<Signal> = implicants.iter()
+                .map(|implicant| {
+                    *terms.entry(*implicant).or_insert_with(|| {
+                        let literals: Vec<Signal> = implicant.literals(num_inputs).into_iter()
+                            .map(|(i, positive)| if positive { Signal::Input(i) } else { builder.not(Signal::Input(i)) })
+                            .collect();
+                        builder.and(&literals)
+                    })
+                })
+                .collect();
+            builder.or(&products)
+        })
+        .collect();
+
+    builder.build(id, name, pins, &outputs)
+}
+
+/// Synthesizes a NAND-only implementation of a definition with a truth table or boolean function.
+#[wasm::wasm_bindgen]
+pub fn synthesize_definition(component_def: wasm::JsValue) -> Result<wasm::JsValue, String> {
+    let component_def: ComponentDefinition = component_def.into_serde().expect("Expected the component definition to be in correct format.");
+    let synthesized = synthesize(&component_def).map_err(|e| e.to_string())?;
+
+    Ok(wasm::JsValue::from_serde(&synthesized).unwrap())
+}
+
+/// Extracts the boolean function of every output of a combinational transparent definition.
+#[wasm::wasm_bindgen]
+pub fn extract_boolean_functions(component_def: wasm::JsValue) -> Result<wasm::JsValue, String> {
+    let component_def: ComponentDefinition = component_def.into_serde().expect("Expected the component definition to be in correct format.");
+    let expressions = REGISTRY.with(|reg| extract_expressions(&reg.lock(), &component_def)).map_err(|e| e.to_string())?;
+
+    Ok(wasm::JsValue::from_serde(&expressions).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::Registry;
+    use crate::circuit::registry::NAND_ID;
+    use crate::component::definition::ComponentKind;
+    use crate::validation::{test_combinational, test_equivalence, CombinationalRequirements, EquivalenceRequirements, ValidationError};
+
+    fn pins(inputs: &[&str], outputs: &[&str]) -> Pins {
+        Pins {
+            input: inputs.iter().map(|x| x.to_string()).collect(),
+            output: outputs.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    fn exhaustive_table(num_inputs: usize, f: impl Fn(&[bool]) -> Vec<bool>) -> TruthTable {
+        let inputs: Vec<Vec<bool>> = (0..1u32 << num_inputs)
+            .map(|row| (0..num_inputs).map(|i| row & (1 << (num_inputs - 1 - i)) != 0).collect())
+            .collect();
+        let outputs = inputs.iter().map(|x| f(x)).collect();
+        TruthTable { inputs, outputs, unstable: Vec::new() }
+    }
+
+    fn assert_implements(def: ComponentDefinition, truth_table: TruthTable) {
+        assert_eq!(def.kind, ComponentKind::Transparent);
+        assert!(def.circuit.as_ref().unwrap().components.iter().all(|x| x.def_id == NAND_ID));
+
+        let report = test_combinational(def, CombinationalRequirements {
+            max_runtime: Some(32),
+            max_delay: None,
+            max_components: None,
+            constraints: Default::default(),
+            truth_table,
+            hidden_rows: Vec::new(),
+            grading: Default::default(),
+            diagnostics: None,
+        });
+        assert!(report.success(), "{:?}", report);
+    }
+
+    #[test]
+    fn minimized_terms() {
+        // f(A, B, C) = sum(0, 1, 2, 5, 6, 7) has two minimal covers of three terms each
+        let implicants = minimize(3, &[0, 1, 2, 5, 6, 7], &[]);
+        assert_eq!(implicants.len(), 3);
+        assert!(implicants.iter().all(|x| x.mask.count_ones() == 1));
+
+        // Don't cares are used to grow terms, but are not covered on their own
+        assert_eq!(minimize(2, &[3], &[1]), vec![Implicant { value: 1, mask: 2 }]);
+        assert_eq!(minimize(2, &[], &[1, 2]), vec![]);
+    }
+
+    #[test]
+    fn full_adder_from_truth_table() {
+        let truth_table = exhaustive_table(3, |x| {
+            let sum = x.iter().filter(|x| **x).count();
+            vec![sum % 2 == 1, sum >= 2]
+        });
+        let def = from_truth_table(1, "FullAdder".into(), pins(&["A", "B", "Cin"], &["S", "Cout"]), &truth_table).unwrap();
+        assert_implements(def, truth_table);
+    }
+
+    #[test]
+    fn constants_and_expressions() {
+        let expressions = [
+            rustlogic::parse("([A]&~[B])|[C]").unwrap(),
+            rustlogic::parse("[A]|~[A]").unwrap(),
+            rustlogic::parse("[B]&~[B]").unwrap(),
+            rustlogic::parse("[B]").unwrap(),
+        ];
+        let def = from_expressions(1, "Mixed".into(), pins(&["A", "B", "C"], &["X", "One", "Zero", "B"]), &expressions).unwrap();
+        let truth_table = exhaustive_table(3, |x| vec![x[0] && !x[1] || x[2], true, false, x[1]]);
+        assert_implements(def, truth_table);
+
+        let result = from_expressions(1, "Unknown".into(), pins(&["A"], &["Y"]), &[rustlogic::parse("[A]&[B]").unwrap()]);
+        assert!(matches!(result, Err(UnknownVariable(x)) if x == "B"));
+    }
+
+    #[test]
+    fn extracted_expressions() {
+        let mut builder = TransparentBuilder::default();
+        let sum = builder.xor(&[Signal::Input(0), Signal::Input(1)]);
+        let carry = builder.and(&[Signal::Input(0), Signal::Input(1)]);
+        let def = builder.build(1, "HalfAdder".into(), pins(&["A", "B"], &["S", "C"]), &[sum, carry]);
+
+        let registry = Registry::default();
+        let expressions = extract_expressions(&registry, &def).unwrap();
+        let exprs: Vec<&str> = expressions.iter().map(|x| x.expr.as_str()).collect();
+        assert_eq!(exprs, ["(~[A]&[B])|([A]&~[B])", "[A]&[B]"]);
+
+        // Synthesized definitions give back their minimized expressions, which parse back
+        let expressions = [
+            rustlogic::parse("([A]&~[B])|[C]").unwrap(),
+            rustlogic::parse("[A]|~[A]").unwrap(),
+            rustlogic::parse("[B]&~[B]").unwrap(),
+        ];
+        let def = from_expressions(1, "Mixed".into(), pins(&["A", "B", "C"], &["X", "One", "Zero"]), &expressions).unwrap();
+        let extracted = extract_expressions(&registry, &def).unwrap();
+        let exprs: Vec<&str> = extracted.iter().map(|x| x.expr.as_str()).collect();
+        assert_eq!(exprs, ["[C]|([A]&~[B])", "1", "0"]);
+
+        for (expected, extracted) in expressions.iter().zip(extracted.iter()) {
+            let parsed = rustlogic::parse(&extracted.expr).unwrap();
+            for minterm in 0..8 {
+                let variables: HashMap<&str, bool> = ["A", "B", "C"].into_iter()
+                    .enumerate()
+                    .map(|(i, name)| (name, minterm & (4 >> i) != 0))
+                    .collect();
+                let value = expected.get_value_from_variables(&variables).unwrap();
+                assert_eq!(extracted.node.get_value_from_variables(&variables).unwrap(), value);
+                assert_eq!(parsed.get_value_from_variables(&variables).unwrap(), value);
+            }
+        }
+    }
+
+    #[test]
+    fn extraction_errors() {
+        let registry = Registry::default();
+        let nand = registry.get_definition(NAND_ID).unwrap();
+        assert!(matches!(extract_expressions(&registry, nand), Err(NotTransparent)));
+
+        // An SR latch feeds back into itself
+        let mut builder = TransparentBuilder::default();
+        let (q, q_inverted) = (builder.net(), builder.net());
+        let set = builder.nand(Signal::Input(0), q_inverted);
+        let reset = builder.nand(Signal::Input(1), q);
+        builder.drive(q, set);
+        builder.drive(q_inverted, reset);
+        let def = builder.build(1, "Latch".into(), pins(&["S", "R"], &["Q"]), &[q]);
+        assert!(matches!(extract_expressions(&registry, &def), Err(NotCombinational(_))));
+    }
+
+    #[test]
+    fn equivalence_counterexample() {
+        let names: Vec<String> = (0..16).map(|i| format!("I{}", i)).collect();
+        let names: Vec<&str> = names.iter().map(|x| x.as_str()).collect();
+        let inputs: Vec<Signal> = (0..16).map(Signal::Input).collect();
+
+        // Parity as a chain of XOR gates, and as a balanced tree of them
+        let mut builder = TransparentBuilder::default();
+        let parity = builder.xor(&inputs);
+        let chain = builder.build(1, "Chain".into(), pins(&names, &["P"]), &[parity]);
+
+        fn tree(builder: &mut TransparentBuilder, inputs: &[Signal]) -> Signal {
+            match inputs {
+                [x] => *x,
+                _ => {
+                    let (left, right) = inputs.split_at(inputs.len() / 2);
+                    let (left, right) = (tree(builder, left), tree(builder, right));
+                    builder.xor(&[left, right])
+                },
+            }
+        }
+        let mut builder = TransparentBuilder::default();
+        let parity = tree(&mut builder, &inputs);
+        let balanced = builder.build(2, "Tree".into(), pins(&names, &["P"]), &[parity]);
+
+        let registry = Registry::default();
+        assert_eq!(check_equivalence(&registry, &chain, &balanced).unwrap(), None);
+
+        // Differs only when all of the inputs are set
+        let mut builder = TransparentBuilder::default();
+        let parity = tree(&mut builder, &inputs);
+        let all = builder.and(&inputs);
+        let output = builder.or(&[parity, all]);
+        let faulty = builder.build(3, "Faulty".into(), pins(&names, &["P"]), &[output]);
+
+        let report = test_equivalence(faulty, EquivalenceRequirements { max_components: None, constraints: Default::default(), reference: chain });
+        assert_eq!(report.errors, vec![ValidationError::IncorrectOutputs {
+            input: vec![true; 16],
+            expected: vec![false],
+            actual: vec![true],
+        }]);
+    }
+}
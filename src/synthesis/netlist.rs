@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use rassert_rs::rassert;
+
+use crate::Circuit;
+use crate::circuit::{Connector, Id, Registry};
+use crate::component::{ComponentDefinition, Ground, Nand, Source, Switch};
+use crate::component::definition::ComponentKind;
+use crate::validation::to_test_circuit_definition;
+use super::SynthesisError;
+use SynthesisError::*;
+
+/// A concrete gate of a flattened definition, referencing its inputs by their index.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Gate {
+    Input(usize),
+    Constant(bool),
+    Nand(usize, usize),
+}
+
+/// The gates of a flattened stateless definition.
+///
+/// Gates are stored in post-order, so the inputs of a gate always come before it.
+#[derive(Debug)]
+pub(crate) struct Netlist {
+    pub num_inputs: usize,
+    pub gates: Vec<Gate>,
+    /// Indices of the gates driving the definition's outputs.
+    pub outputs: Vec<usize>,
+}
+
+impl Netlist {
+    /// Flattens a transparent definition and traces its outputs back to the input pins.
+    ///
+    /// Definitions containing feedback loops or components other than NAND gates and constants
+    /// are rejected, and unconnected inputs of NAND gates read as 0.
+    pub fn trace(registry: &Registry, def: &ComponentDefinition) -> Result<Self, SynthesisError> {
+        rassert!(def.kind == ComponentKind::Transparent, NotTransparent);
+
+        // Flatten the definition between switches and LEDs, the same way it is validated
+        let mut registry = registry.clone();
+        let circuit_def = to_test_circuit_definition(&mut registry, def.clone()).map_err(|_| NotTransparent)?;
+        let circuit = Circuit::from_definition(&registry, circuit_def)?;
+
+        let mut drivers: HashMap<Connector, Vec<Connector>> = HashMap::new();
+        for (&from, to) in circuit.connections.iter() {
+            to.iter().for_each(|&sink| drivers.entry(sink).or_default().push(from));
+        }
+
+        let num_inputs = def.pins.input.len();
+        let mut tracer = Tracer { circuit: &circuit, drivers, gates: Vec::new(), traced: HashMap::new(), tracing: Vec::new() };
+        let outputs = (0..def.pins.output.len())
+            .map(|i| tracer.driver(Connector::new((num_inputs + i + 1) as Id, 0)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { num_inputs, gates: tracer.gates, outputs })
+    }
+
+    /// Evaluates the outputs for the input values.
+    pub fn evaluate(&self, inputs: &[bool]) -> Vec<bool> {
+        let mut values = vec![false; self.gates.len()];
+        self.evaluate_into(|i| inputs[i], &mut values);
+        self.outputs.iter().map(|&x| values[x]).collect()
+    }
+
+    /// Evaluates every gate, storing the results into `values`.
+    pub fn evaluate_into(&self, input: impl Fn(usize) -> bool, values: &mut [bool]) {
+        for (i, gate) in self.gates.iter().enumerate() {
+            values[i] = match *gate {
+                Gate::Input(x) => input(x),
+                Gate::Constant(value) => value,
+                Gate::Nand(a, b) => !(values[a] && values[b]),
+            };
+        }
+    }
+}
+
+struct Tracer<'a> {
+    circuit: &'a Circuit,
+    /// Maps every input connector to the output connectors driving it.
+    drivers: HashMap<Connector, Vec<Connector>>,
+    gates: Vec<Gate>,
+    traced: HashMap<Id, usize>,
+    tracing: Vec<Id>,
+}
+
+impl Tracer<'_> {
+    fn path(&self, id: Id) -> String {
+        self.circuit.hierarchy.path_of(id).map(|x| x.to_string()).unwrap_or_else(|| id.to_string())
+    }
+
+    /// Returns the gate driving the input connector.
+    fn driver(&mut self, sink: Connector) -> Result<usize, SynthesisError> {
+        match self.drivers.get(&sink).map(|x| x.as_slice()).unwrap_or_default() {
+            [] => {
+                self.gates.push(Gate::Constant(false));
+                Ok(self.gates.len() - 1)
+            },
+            &[driver] => self.component(driver.component),
+            _ => Err(MultipleDrivers(self.path(sink.component))),
+        }
+    }
+
+    fn component(&mut self, id: Id) -> Result<usize, SynthesisError> {
+        if let Some(&gate) = self.traced.get(&id) {
+            return Ok(gate);
+        }
+        rassert!(!self.tracing.contains(&id), NotCombinational(self.path(id)));
+
+        let component = self.circuit.components[&id].as_any();
+        let gate = if component.is::<Nand>() {
+            self.tracing.push(id);
+            let a = self.driver(Connector::new(id, 0))?;
+            let b = self.driver(Connector::new(id, 1))?;
+            self.tracing.pop();
+            Gate::Nand(a, b)
+        } else if component.is::<Switch>() {
+            // Switches of the test circuit take IDs right after the definition's component
+            Gate::Input(id as usize - 1)
+        } else if component.is::<Source>() {
+            Gate::Constant(true)
+        } else if component.is::<Ground>() {
+            Gate::Constant(false)
+        } else {
+            return Err(UnsupportedComponent(self.path(id)));
+        };
+
+        self.gates.push(gate);
+        self.traced.insert(id, self.gates.len() - 1);
+        Ok(self.gates.len() - 1)
+    }
+}
@@ -1,3 +1,5 @@
 mod bits;
+mod rng;
 pub(crate) mod bitext;
 pub use bits::*;
+pub use rng::Rng;
@@ -0,0 +1,36 @@
+/// A small deterministic pseudo-random number generator (SplitMix64), so that randomized tests
+/// can be reproduced from their seed.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        Self { state: seed as u64 }
+    }
+
+    /// Returns a seed which differs between calls, taken from the platform's randomness.
+    pub fn random_seed() -> u32 {
+        if cfg!(target_arch = "wasm32") {
+            (js_sys::Math::random() * u32::MAX as f64) as u32
+        } else {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|x| x.subsec_nanos() ^ x.as_secs() as u32)
+                .unwrap_or_default()
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_bool(&mut self) -> bool {
+        self.next_u64() >> 63 == 1
+    }
+}
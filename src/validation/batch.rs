@@ -0,0 +1,155 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use rassert_rs::rassert;
+
+use crate::wasm;
+use crate::circuit::Registry;
+use crate::circuit::registry::REGISTRY;
+use crate::component::definition::ComponentDefinition;
+use super::{test_combinational_in, CombinationalRequirements, ValidationReport};
+
+/// A definition submitted for grading, identified by an ID unique within its batch.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Submission {
+    pub id: String,
+    pub definition: ComponentDefinition,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BatchError {
+    #[error("Submission ID '{0}' is used more than once.")]
+    DuplicateId(String),
+}
+
+#[wasm::wasm_bindgen(js_name = "test_combinational_batch")]
+pub fn js_test_combinational_batch(requirements: wasm::JsValue, submissions: wasm::JsValue) -> Result<wasm::JsValue, String> {
+    let requirements = requirements.into_serde().expect("Expected the combinational requirements to be in correct format.");
+    let submissions = submissions.into_serde().expect("Expected the submissions to be in correct format.");
+
+    let registry = Arc::new(REGISTRY.with(|reg| reg.lock().clone()));
+    let reports = grade_batch(registry, &requirements, submissions, None).map_err(|e| e.to_string())?;
+    Ok(wasm::JsValue::from_serde(&reports).unwrap())
+}
+
+/// Tests every submission against the same requirements, resolving the definitions they use in
+/// the registry.
+///
+/// On native targets, the submissions run in parallel on a thread per core. A submission which
+/// is still running after `timeout` fails with [`ValidationError::TimedOut`], and its simulation
+/// is cancelled. Its thread keeps taking up a worker until it has stopped. On wasm, the
+/// submissions run one after another and the timeout is ignored.
+///
+/// [`ValidationError::TimedOut`]: super::ValidationError::TimedOut
+///
+/// # Returns
+/// The report of each submission by its ID, or [`BatchError::DuplicateId`] before grading
+/// anything if two submissions share an ID.
+pub fn grade_batch(registry: Arc<Registry>, requirements: &CombinationalRequirements, submissions: Vec<Submission>, timeout: Option<Duration>) -> Result<HashMap<String, ValidationReport>, BatchError> {
+    let mut ids = HashSet::new();
+    for submission in submissions.iter() {
+        rassert!(ids.insert(submission.id.as_str()), BatchError::DuplicateId(submission.id.clone()));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    return Ok(native::grade_batch(registry, requirements, submissions, timeout));
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = timeout;
+        Ok(submissions.into_iter()
+            .map(|x| (x.id, test_combinational_in(&registry, x.definition, requirements, None)))
+            .collect())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::panic::{self, AssertUnwindSafe};
+    use std::collections::HashSet;
+    use std::sync::mpsc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use std::time::Instant;
+
+    use super::*;
+    use crate::validation::ValidationError;
+
+    pub(super) fn grade_batch(registry: Arc<Registry>, requirements: &CombinationalRequirements, submissions: Vec<Submission>, timeout: Option<Duration>) -> HashMap<String, ValidationReport> {
+        let requirements = Arc::new(requirements.clone());
+        let num_workers = thread::available_parallelism().map_or(1, |x| x.get());
+
+        let (sender, receiver) = mpsc::channel();
+        let mut pending = submissions.into_iter().enumerate();
+        let mut running: HashMap<usize, (String, Instant, Arc<AtomicBool>)> = HashMap::new();
+        // Submissions which timed out, whose threads have not stopped yet
+        let mut cancelled = HashSet::new();
+        let mut reports = HashMap::new();
+        loop {
+            while running.len() + cancelled.len() < num_workers {
+                let Some((index, submission)) = pending.next() else {
+                    break;
+                };
+
+                let cancel = Arc::new(AtomicBool::new(false));
+                running.insert(index, (submission.id, Instant::now(), cancel.clone()));
+                let (registry, requirements, sender) = (registry.clone(), requirements.clone(), sender.clone());
+                thread::spawn(move || {
+                    let report = panic::catch_unwind(AssertUnwindSafe(|| test_combinational_in(&registry, submission.definition, &requirements, Some(cancel))))
+                        .unwrap_or_else(|e| failed(ValidationError::AnalysisFailed { reason: panic_message(e) }));
+
+                    // The batch no longer waits for submissions which timed out
+                    let _ = sender.send((index, report));
+                });
+            }
+
+            // Cancelled submissions are not waited for once nothing else is left
+            if running.is_empty() && pending.len() == 0 {
+                break;
+            }
+
+            // Waits for the next report, or until the oldest running submission times out
+            let deadline = timeout.and_then(|timeout| running.values().map(|(_, started, _)| *started + timeout).min());
+            let received = match deadline {
+                Some(deadline) => receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())).ok(),
+                None => receiver.recv().ok(),
+            };
+
+            if let Some((index, report)) = received {
+                if let Some((id, _, _)) = running.remove(&index) {
+                    reports.insert(id, report);
+                } else {
+                    cancelled.remove(&index);
+                }
+            } else if let Some(timeout) = timeout {
+                let now = Instant::now();
+                let timed_out: Vec<usize> = running.iter()
+                    .filter(|(_, (_, started, _))| now.duration_since(*started) >= timeout)
+                    .map(|(&index, _)| index)
+                    .collect();
+                for index in timed_out {
+                    let (id, _, cancel) = running.remove(&index).unwrap();
+                    cancel.store(true, Ordering::Relaxed);
+                    cancelled.insert(index);
+                    reports.insert(id, failed(ValidationError::TimedOut { limit_ms: timeout.as_millis() as u64 }));
+                }
+            }
+        }
+
+        reports
+    }
+
+    fn failed(error: ValidationError) -> ValidationReport {
+        let mut report = ValidationReport { errors: vec![error], ..Default::default() };
+        report.grade_pass_fail();
+        report
+    }
+
+    fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+        match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => payload.downcast_ref::<&str>().map_or_else(|| "Validation panicked".into(), |x| x.to_string()),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::circuit::Registry;
+use crate::component::definition::{ComponentDefinition, ComponentKind};
+use super::{Constraints, ValidationError, ValidationReport};
+
+/// The components a definition is made of, through all of its transparent sub-definitions.
+#[derive(Debug, Clone, Default)]
+struct Usage {
+    /// Number of instances of each builtin, by definition ID.
+    builtins: HashMap<i32, u32>,
+    /// IDs of all the definitions used in the hierarchy.
+    definitions: HashSet<i32>,
+    /// Nesting depth, where a definition made only of builtins has depth 1.
+    depth: u32,
+}
+
+/// Checks the definition against the constraints, pushing an error for each violation.
+///
+/// Unknown definition IDs and recursive definitions are skipped, since they are reported when
+/// building the definition.
+pub(crate) fn check_constraints(report: &mut ValidationReport, registry: &Registry, component_def: &ComponentDefinition, constraints: &Constraints) {
+    let mut memo = HashMap::new();
+    let usage = usage(registry, component_def, &mut vec![component_def.id], &mut memo);
+
+    let mut limits: Vec<_> = constraints.max_builtins.iter().collect();
+    limits.sort();
+    for (&def_id, &max_allowed) in limits {
+        let used = usage.builtins.get(&def_id).copied().unwrap_or_default();
+        if used > max_allowed {
+            report.errors.push(ValidationError::MaxBuiltinsExceeded { def_id, used, max_allowed });
+        }
+    }
+
+    for &def_id in constraints.forbidden_definitions.iter() {
+        if usage.definitions.contains(&def_id) {
+            report.errors.push(ValidationError::ForbiddenDefinition { def_id });
+        }
+    }
+
+    if let Some(max_allowed) = constraints.max_depth {
+        if usage.depth > max_allowed {
+            report.errors.push(ValidationError::MaxDepthExceeded { depth: usage.depth, max_allowed });
+        }
+    }
+}
+
+fn usage(registry: &Registry, def: &ComponentDefinition, path: &mut Vec<i32>, memo: &mut HashMap<i32, Usage>) -> Usage {
+    let mut usage = Usage { depth: 1, ..Default::default() };
+    for dependency in def.dependencies() {
+        usage.definitions.insert(dependency);
+        let Ok(sub_def) = registry.get_definition(dependency) else {
+            continue;
+        };
+
+        if sub_def.kind != ComponentKind::Transparent {
+            *usage.builtins.entry(dependency).or_default() += 1;
+            continue;
+        }
+        if path.contains(&dependency) {
+            continue;
+        }
+
+        // Sub-definitions are shared between instances, so each is only traversed once
+        if !memo.contains_key(&dependency) {
+            path.push(dependency);
+            let sub_usage = self::usage(registry, sub_def, path, memo);
+            path.pop();
+            memo.insert(dependency, sub_usage);
+        }
+
+        let sub_usage = &memo[&dependency];
+        for (&id, &count) in sub_usage.builtins.iter() {
+            let total = usage.builtins.entry(id).or_default();
+            *total = total.saturating_add(count);
+        }
+        usage.definitions.extend(sub_usage.definitions.iter().copied());
+        usage.depth = usage.depth.max(sub_usage.depth + 1);
+    }
+
+    usage
+}
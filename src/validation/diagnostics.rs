@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+use crate::circuit::{Connector, InspectionError};
+use super::{Hint, TestHarness};
+
+/// Finds hints explaining the failing outputs, from the expected and actual outputs of the given
+/// rows of the truth table.
+pub(crate) fn diagnose_outputs(rows: &[usize], expected: &[Vec<bool>], actual: &[Vec<bool>]) -> Vec<Hint> {
+    let num_outputs = expected.first().map_or(0, |x| x.len());
+
+    let mut hints = Vec::new();
+    for output in 0..num_outputs {
+        let wrong: Vec<usize> = (0..expected.len()).filter(|&i| expected[i][output] != actual[i][output]).collect();
+        if wrong.is_empty() {
+            continue;
+        }
+
+        let constant = |outputs: &[Vec<bool>]| outputs.iter().all(|x| x[output] == outputs[0][output]);
+        let inverted = wrong.len() == expected.len();
+        hints.push(Hint::WrongOutput { output, rows: wrong.into_iter().map(|i| rows[i]).collect() });
+
+        if constant(actual) && !constant(expected) {
+            hints.push(Hint::ConstantOutput { output, value: actual[0][output] });
+        }
+        if inverted {
+            hints.push(Hint::InvertedOutput { output });
+        }
+    }
+
+    hints
+}
+
+/// Finds the internal nets of the definition whose values differ from those of the reference
+/// after both have run, ignoring nets which only one of them has.
+pub(crate) fn differing_nets(reference: &TestHarness, harness: &TestHarness) -> Result<Vec<Connector>, InspectionError> {
+    let expected: HashMap<Connector, Option<bool>> = reference.inner_state()?.into_iter().collect();
+    Ok(harness.inner_state()?.into_iter()
+        .filter(|(at, value)| expected.get(at).is_some_and(|x| x != value))
+        .map(|(at, _)| at)
+        .collect())
+}
@@ -0,0 +1,213 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use crate::circuit::registry::REGISTRY;
+use crate::component::definition::ComponentDefinition;
+use super::{definition_harness, validate_definition, FsmRequirements, TestHarness, ValidationError, ValidationReport};
+
+/// Tests a sequential definition against a Moore state machine.
+///
+/// Transitions are explored breadth-first from the initial state, replaying the shortest known
+/// path from a reset to a state before taking each of its transitions. Inputs return to their
+/// idle values after every transition, when the circuit's state is sampled from its outputs and
+/// the values in its feedback loops. The encoding of the states is inferred from these samples,
+/// so that transitions into the wrong state are detected even if its outputs are the same.
+///
+/// With a reset, the clock is pulsed while the reset input is held active, which resets both
+/// synchronous and asynchronous designs.
+pub fn test_fsm(component_def: ComponentDefinition, requirements: FsmRequirements) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    // The interface is given by the transitions and states, if there are any
+    let num_inputs = requirements.transitions.first().map(|x| x.inputs.len()).unwrap_or(component_def.pins.input.len());
+    let num_outputs = requirements.states.first().map(|x| x.outputs.len()).unwrap_or(component_def.pins.output.len());
+
+    // Validate test requirements
+    let states: HashMap<&str, usize> = requirements.states.iter().enumerate().map(|(i, x)| (x.name.as_str(), i)).collect();
+    assert!(states.contains_key(requirements.initial_state.as_str()), "Initial state is not a state!");
+    assert!(requirements.states.iter().all(|x| x.outputs.len() == num_outputs), "States have a different number of outputs!");
+    for transition in requirements.transitions.iter() {
+        assert!(states.contains_key(transition.from.as_str()) && states.contains_key(transition.to.as_str()), "Transition between unknown states!");
+        assert!(transition.inputs.len() == num_inputs, "Transitions have a different number of inputs!");
+    }
+    assert!(requirements.clock < num_inputs, "Clock input is out of range!");
+    if let Some(reset) = requirements.reset.as_ref() {
+        assert!(reset.input < num_inputs, "Reset input is out of range!");
+    }
+
+    let temp_registry = Arc::new(REGISTRY.with(|reg| reg.lock().clone()));
+
+    validate_definition(&mut report, &temp_registry, &component_def, requirements.max_components, &requirements.constraints, num_inputs, num_outputs);
+    if report.failure() {
+        return report;
+    }
+
+    let Some(harness) = definition_harness(&mut report, &temp_registry, component_def) else {
+        return report;
+    };
+    let mut explorer = Explorer {
+        harness,
+        requirements: &requirements,
+        num_inputs,
+    };
+    explorer.harness.enable_inspection();
+
+    // Circuit states sampled after reaching each state, and the shortest paths to the states
+    let mut encoding: HashMap<Vec<bool>, usize> = HashMap::new();
+    let mut paths: Vec<Option<Vec<Vec<bool>>>> = vec![None; requirements.states.len()];
+    let mut queue = VecDeque::new();
+
+    let initial = states[requirements.initial_state.as_str()];
+    let (sample, outputs) = explorer.run(&[]);
+    let expected = &requirements.states[initial].outputs;
+    if !outputs_match(expected, &outputs) {
+        report.errors.push(ValidationError::IncorrectStateOutputs {
+            state: requirements.initial_state.clone(),
+            expected: expected.clone(),
+            actual: outputs,
+        });
+    }
+    encoding.insert(sample, initial);
+    paths[initial] = Some(Vec::new());
+    queue.push_back(initial);
+
+    while let Some(state) = queue.pop_front() {
+        let from = &requirements.states[state].name;
+        for transition in requirements.transitions.iter().filter(|x| x.from == *from) {
+            let to = states[transition.to.as_str()];
+            for input in explorer.expand(&transition.inputs) {
+                let mut path = paths[state].clone().unwrap();
+                path.push(input.clone());
+
+                let (sample, outputs) = explorer.run(&path);
+                let error = match encoding.get(&sample) {
+                    Some(&actual) if actual == to => None,
+                    Some(&actual) if actual == state => Some(ValidationError::MissingTransition {
+                        from: from.clone(),
+                        input,
+                        expected: transition.to.clone(),
+                    }),
+                    actual => {
+                        // Circuits may encode a state in more than one way, which is only known
+                        // to be wrong if the outputs differ
+                        if actual.is_none() && outputs_match(&requirements.states[to].outputs, &outputs) {
+                            encoding.insert(sample, to);
+                            if paths[to].is_none() {
+                                paths[to] = Some(path);
+                                queue.push_back(to);
+                            }
+                            None
+                        } else {
+                            Some(ValidationError::WrongTransition {
+                                from: from.clone(),
+                                input,
+                                expected: transition.to.clone(),
+                                actual: actual.map(|&x| requirements.states[x].name.clone()),
+                            })
+                        }
+                    },
+                };
+                report.errors.extend(error);
+            }
+        }
+    }
+
+    for (state, path) in requirements.states.iter().zip(paths.iter()) {
+        if path.is_none() {
+            report.errors.push(ValidationError::UnreachedState { state: state.name.clone() });
+        }
+    }
+
+    report.grade_pass_fail();
+    report
+}
+
+fn outputs_match(expected: &[Option<bool>], actual: &[bool]) -> bool {
+    expected.iter().zip(actual.iter()).all(|(expected, actual)| expected.is_none_or(|x| x == *actual))
+}
+
+struct Explorer<'a> {
+    harness: TestHarness,
+    requirements: &'a FsmRequirements,
+    num_inputs: usize,
+}
+
+impl Explorer<'_> {
+    /// Returns the input values while no transition is taken, which keep the reset inactive.
+    fn idle(&self) -> Vec<bool> {
+        let mut idle = vec![false; self.num_inputs];
+        if let Some(reset) = self.requirements.reset.as_ref() {
+            idle[reset.input] = reset.active_low;
+        }
+        idle
+    }
+
+    /// Expands a transition's condition into all the inputs satisfying it.
+    fn expand(&self, condition: &[Option<bool>]) -> Vec<Vec<bool>> {
+        let reset = self.requirements.reset.as_ref().map(|x| x.input);
+        let mut inputs = vec![self.idle()];
+        for (i, &value) in condition.iter().enumerate() {
+            if i == self.requirements.clock || Some(i) == reset {
+                continue;
+            }
+
+            match value {
+                Some(value) => inputs.iter_mut().for_each(|x| x[i] = value),
+                None => {
+                    let mut set = inputs.clone();
+                    set.iter_mut().for_each(|x| x[i] = true);
+                    inputs.append(&mut set);
+                },
+            }
+        }
+        inputs
+    }
+
+    fn set_inputs(&mut self, inputs: &[bool]) {
+        for (i, &value) in inputs.iter().enumerate() {
+            if i != self.requirements.clock {
+                self.harness.set_input(i, value);
+            }
+        }
+    }
+
+    fn pulse_clock(&mut self) {
+        let ticks = self.requirements.clock_ticks as usize;
+        self.harness.set_input(self.requirements.clock, true);
+        self.harness.tick_for(ticks);
+        self.harness.set_input(self.requirements.clock, false);
+        self.harness.tick_for(ticks);
+    }
+
+    /// Resets the circuit and takes a transition for each of the inputs.
+    ///
+    /// # Returns
+    /// The sampled circuit state and the outputs in it.
+    fn run(&mut self, path: &[Vec<bool>]) -> (Vec<bool>, Vec<bool>) {
+        let ticks = self.requirements.clock_ticks as usize;
+        let idle = self.idle();
+        self.harness.apply(&idle);
+        self.harness.tick_for(ticks);
+
+        if let Some(reset) = self.requirements.reset.as_ref() {
+            self.harness.set_input(reset.input, !reset.active_low);
+            self.harness.tick_for(reset.ticks as usize);
+            self.pulse_clock();
+            self.harness.set_input(reset.input, reset.active_low);
+            self.harness.tick_for(reset.ticks as usize);
+        }
+
+        for inputs in path {
+            self.set_inputs(inputs);
+            self.harness.tick_for(ticks);
+            self.pulse_clock();
+            self.set_inputs(&idle);
+            self.harness.tick_for(ticks);
+        }
+
+        let outputs = self.harness.outputs();
+        let mut sample = outputs.clone();
+        sample.extend(self.harness.latch_values().expect("Inspection is enabled for the explorer"));
+        (sample, outputs)
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{Circuit, Simulation};
+use crate::circuit::{ComponentPath, Connector, Id, InspectionError, Registry};
+use crate::sim::Event;
+use crate::component::{Led, Switch};
+use crate::component::definition::ComponentDefinition;
+use super::{to_test_circuit_definition, ConversionError};
+
+/// The number of ticks after which a simulation that has not settled is considered unstable.
+pub const MAX_SETTLE_TICKS: usize = 10_000;
+
+/// Simulates a transparent definition with its inputs driven by switches and its outputs read
+/// through LEDs.
+#[derive(Debug)]
+pub struct TestHarness {
+    sim: Simulation,
+    num_inputs: usize,
+    num_outputs: usize,
+    /// Outputs of the components which are part of feedback loops, sorted.
+    latches: Vec<Connector>,
+    /// Stops the simulation once set, e.g. when the test timed out.
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl TestHarness {
+    /// Builds the harness, adding the definition to an overlay of the shared registry instead of
+    /// copying it.
+    pub fn new(registry: &Arc<Registry>, component_def: ComponentDefinition) -> Result<Self, ConversionError> {
+        let num_inputs = component_def.pins.input.len();
+        let num_outputs = component_def.pins.output.len();
+
+        let mut registry = Registry::overlay(registry.clone());
+        let circuit_def = to_test_circuit_definition(&mut registry, component_def)?;
+        let sim = Simulation {
+            circuit: Circuit::from_definition(&registry, circuit_def)?,
+            ..Default::default()
+        };
+
+        let latches = find_latches(&sim.circuit);
+        Ok(Self { sim, num_inputs, num_outputs, latches, cancel: None })
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.num_inputs
+    }
+
+    pub fn num_outputs(&self) -> usize {
+        self.num_outputs
+    }
+
+    /// Stops advancing the simulation once the flag is set.
+    pub fn set_cancel(&mut self, cancel: Arc<AtomicBool>) {
+        self.cancel = Some(cancel);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|x| x.load(Ordering::Relaxed))
+    }
+
+    /// Records the values on the nets, which the latch values and inner state are read from.
+    pub fn enable_inspection(&mut self) {
+        self.sim.enable_inspection();
+    }
+
+    /// Resets the simulation and applies the input values, without advancing it.
+    pub fn apply(&mut self, inputs: &[bool]) {
+        debug_assert_eq!(inputs.len(), self.num_inputs);
+        self.sim.reset();
+
+        // Switches take the IDs right after the tested component, followed by the LEDs
+        for (i, &input) in inputs.iter().enumerate() {
+            let id = (i + 1) as Id;
+            let switch = self.sim.circuit.components.get_mut(&id).unwrap().as_any_mut().downcast_mut::<Switch>().unwrap();
+            switch.output = input;
+        }
+
+        self.sim.init();
+    }
+
+    /// Changes the value of an input while the simulation is running.
+    pub fn set_input(&mut self, input: usize, value: bool) {
+        let id = (input + 1) as Id;
+        let switch = self.sim.circuit.components.get_mut(&id).unwrap().as_any_mut().downcast_mut::<Switch>().unwrap();
+        if switch.output != value {
+            switch.output = value;
+            self.sim.wheel.schedule(0, Event::new(value, Connector::new(id, 0)));
+        }
+    }
+
+    /// Advances the simulation, stopping early once it has settled since nothing can change
+    /// afterwards, or once it is cancelled.
+    pub fn tick_for(&mut self, num_ticks: usize) {
+        for _ in 0..num_ticks {
+            if self.sim.is_settled() || self.is_cancelled() {
+                break;
+            }
+            self.sim.tick();
+        }
+    }
+
+    /// Advances the simulation until no more events are scheduled.
+    ///
+    /// # Returns
+    /// The number of ticks it took to settle, or `None` if the simulation did not settle within
+    /// `max_ticks`, e.g. because the circuit oscillates, or if it was cancelled.
+    pub fn settle(&mut self, max_ticks: usize) -> Option<usize> {
+        for ticks in 0..=max_ticks {
+            if self.sim.is_settled() {
+                return Some(ticks);
+            }
+            if self.is_cancelled() {
+                return None;
+            }
+            self.sim.tick();
+        }
+
+        None
+    }
+
+    /// Returns the last values emitted by the components which are part of feedback loops, which
+    /// together with the outputs make up the circuit's state. Latches which have not emitted
+    /// anything yet read as 0, same as their inputs do.
+    ///
+    /// # Errors
+    /// [`InspectionError::NotEnabled`] unless [`Self::enable_inspection`] was called first.
+    pub fn latch_values(&self) -> Result<Vec<bool>, InspectionError> {
+        let nets = self.sim.nets.as_ref().ok_or(InspectionError::NotEnabled)?;
+        Ok(self.latches.iter().map(|x| nets.get(x).copied().unwrap_or_default()).collect())
+    }
+
+    /// Returns the values on the internal connections of the tested definition, keyed by the
+    /// connectors local to it.
+    ///
+    /// # Errors
+    /// [`InspectionError::NotEnabled`] unless [`Self::enable_inspection`] was called first.
+    pub fn inner_state(&self) -> Result<Vec<(Connector, Option<bool>)>, InspectionError> {
+        self.sim.inner_state(&ComponentPath::top_level(0))
+    }
+
+    /// Returns the values of the LEDs.
+    pub fn outputs(&self) -> Vec<bool> {
+        (0..self.num_outputs)
+            .map(|i| {
+                let id = (i + self.num_inputs + 1) as Id;
+                self.sim.circuit.components.get(&id).unwrap().as_any().downcast_ref::<Led>().unwrap().value
+            })
+            .collect()
+    }
+}
+
+/// Finds the output connectors of components that can reach themselves through the connections.
+fn find_latches(circuit: &Circuit) -> Vec<Connector> {
+    let mut successors: HashMap<Id, Vec<Id>> = HashMap::new();
+    for (from, to) in circuit.connections.iter() {
+        successors.entry(from.component).or_default().extend(to.iter().map(|x| x.component));
+    }
+
+    let in_loop = |start: Id| {
+        let mut visited = HashSet::new();
+        let mut stack = successors.get(&start).cloned().unwrap_or_default();
+        while let Some(id) = stack.pop() {
+            if id == start {
+                return true;
+            }
+            if visited.insert(id) {
+                stack.extend(successors.get(&id).into_iter().flatten());
+            }
+        }
+        false
+    };
+
+    let mut latches: Vec<Connector> = circuit.connections.keys()
+        .filter(|x| in_loop(x.component))
+        .copied()
+        .collect();
+    latches.sort_by_key(|x| (x.component, x.pin));
+    latches
+}
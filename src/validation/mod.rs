@@ -1,13 +1,27 @@
 mod requirements;
 mod report;
-pub use requirements::CombinationalRequirements;
-pub use report::{ValidationReport, ValidationError, ConnectorKind};
+mod harness;
+mod fsm;
+mod constraints;
+mod diagnostics;
+mod batch;
+pub use requirements::{CombinationalRequirements, EquivalenceRequirements, SequentialRequirements, Reset, Step};
+pub use requirements::{FsmRequirements, FsmState, FsmTransition, Grading, TestGroup, ComponentPenalty, Constraints};
+pub use requirements::{RandomRequirements, Reference, ReferenceRequirements, Stimulus, Diagnostics};
+pub use report::{ValidationReport, ValidationError, ConnectorKind, GroupResult, Hint};
+pub use harness::{TestHarness, MAX_SETTLE_TICKS};
+pub use fsm::test_fsm;
+pub use batch::{grade_batch, BatchError, Submission};
 
-use crate::component::{Led, Switch};
-use crate::{Simulation, Circuit, wasm};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use crate::wasm;
 use crate::circuit::registry::{SWITCH_ID, LED_ID, REGISTRY};
-use crate::component::definition::{ComponentDefinition, ComponentKind, Component};
-use crate::circuit::{CircuitDefinition, Registry, Connection, Connector};
+use crate::component::definition::{ComponentDefinition, ComponentKind, Component, TruthTable, MAX_TABLE_INPUTS};
+use crate::circuit::{CircuitDefinition, DefinitionError, Registry, Connection, Connector};
+use crate::synthesis::check_equivalence;
+use crate::util::Rng;
 use ConversionError::*;
 use rassert_rs::rassert;
 
@@ -20,93 +34,572 @@ pub fn js_test_combinational(component_def: wasm::JsValue, requirements: wasm::J
 }
 
 pub fn test_combinational(component_def: ComponentDefinition, requirements: CombinationalRequirements) -> ValidationReport {
+    // Construct the temporary registry
+    let temp_registry = Arc::new(REGISTRY.with(|reg| reg.lock().clone()));
+
+    test_combinational_in(&temp_registry, component_def, &requirements, None)
+}
+
+/// Tests the definition against the truth table, resolving the definitions it uses in the
+/// registry. Once `cancel` is set, the simulation stops and the test returns what it has so far.
+pub(crate) fn test_combinational_in(registry: &Arc<Registry>, component_def: ComponentDefinition, requirements: &CombinationalRequirements, cancel: Option<Arc<AtomicBool>>) -> ValidationReport {
     let mut report = ValidationReport::default();
 
     // Validate test requirements
     assert!(!(requirements.truth_table.inputs.is_empty() || requirements.truth_table.outputs.is_empty()), "Truth table is empty!");
+    let mut diagnostics_reference = match requirements.diagnostics.as_ref().and_then(|x| x.reference) {
+        Some(reference) => match reference_harness(registry, reference) {
+            Ok(reference) => Some(reference),
+            Err(reason) => {
+                report.errors.push(ValidationError::InvalidRequirements { reason });
+                return report;
+            },
+        },
+        None => None,
+    };
 
-    // Validate component definition (capture all related errors and return if any of them failed
-    // afterwards)
-    let used = component_def.circuit.as_ref().unwrap().components.len() as u32;
-    let max_allowed = requirements.max_components.unwrap_or(u32::MAX);
-    if !(used <= max_allowed) {
-        report.errors.push(ValidationError::MaxComponentsExceeded { used, max_allowed });
-    }
-
-    if !(component_def.pins.input.len() == requirements.truth_table.inputs[0].len()) {
-        report.errors.push(ValidationError::InvalidComponentInterface { 
-            kind: ConnectorKind::Input,
-            expected: requirements.truth_table.inputs[0].len() as u32, 
-            actual: component_def.pins.input.len() as u32,
-        });
-    } else if !(component_def.pins.output.len() == requirements.truth_table.outputs[0].len()) {
-        report.errors.push(ValidationError::InvalidComponentInterface { 
-            kind: ConnectorKind::Output,
-            expected: requirements.truth_table.outputs[0].len() as u32, 
-            actual: component_def.pins.output.len() as u32,
-        });
-    }
+    // Validate component definition
+    let num_inputs = requirements.truth_table.inputs[0].len();
+    let num_outputs = requirements.truth_table.outputs[0].len();
+    validate_definition(&mut report, registry, &component_def, requirements.max_components, &requirements.constraints, num_inputs, num_outputs);
 
     // If any of the component definition validation failed, early exit
     if report.failure() {
         return report;
     }
 
-    // Construct the temporary registry
-    let mut temp_registry = Registry::default();
-    REGISTRY.with(|reg| temp_registry = reg.lock().clone());
-
-    // Construct the test circuit definition
-    let circuit_def = to_test_circuit_definition(&mut temp_registry, component_def.clone()).unwrap();
-    let circuit = Circuit::from_definition(&temp_registry, circuit_def).unwrap();
+    // Construct the test harness
+    let num_components = component_def.circuit.as_ref().unwrap().components.len() as u32;
+    let Some(mut harness) = definition_harness(&mut report, registry, component_def) else {
+        return report;
+    };
+    if let Some(cancel) = cancel {
+        if let Some(reference) = diagnostics_reference.as_mut() {
+            reference.set_cancel(cancel.clone());
+        }
+        harness.set_cancel(cancel);
+    }
 
-    // Construct the simulation
-    let mut ctx = Simulation::default();
-    ctx.circuit = circuit;
+    let mut failed_rows = Vec::new();
+    let mut slowest: Option<(u32, usize)> = None;
+    // Rows seen by the diagnostics, which may not reveal the hidden ones
+    let (mut visible_rows, mut expected_rows, mut actual_rows) = (Vec::new(), Vec::new(), Vec::new());
+    let mut first_incorrect = None;
+    for (row, (inputs, expected_outputs)) in requirements.truth_table.iter().enumerate() {
+        if harness.is_cancelled() {
+            return report;
+        }
 
-    for (inputs, expected_outputs) in requirements.truth_table.iter() {
-        // Set inputs
-        for (i, &input) in inputs.iter().enumerate() {
-            let id = (i + 1) as u32;
-            let switch = ctx.circuit.components.get_mut(&id).unwrap().as_any_mut().downcast_mut::<Switch>().unwrap();
-            switch.output = input;
+        // Measure the delay separately, so that the outputs are still read after the runtime
+        if let Some(max_delay) = requirements.max_delay {
+            harness.apply(inputs);
+            let measured = harness.settle(MAX_SETTLE_TICKS).unwrap_or(MAX_SETTLE_TICKS) as u32;
+            if measured > max_delay {
+                failed_rows.push(row);
+            }
+            if slowest.is_none_or(|(ticks, _)| measured > ticks) {
+                slowest = Some((measured, row));
+            }
         }
 
-        ctx.init();
+        harness.apply(inputs);
 
         // Advance the simulation
         if let Some(max_runtime) = requirements.max_runtime {
-            ctx.tick_for((max_runtime + 2) as usize);
+            harness.tick_for((max_runtime + 2) as usize);
         } else {
             unimplemented!()
         }
 
-        // Get outputs
-        let mut actual_outputs = Vec::with_capacity(expected_outputs.len());
-        for (i, _) in expected_outputs.iter().enumerate() {
-            let id = (i + inputs.len() + 1) as u32;
-            let led = ctx.circuit.components.get(&id).unwrap().as_any().downcast_ref::<Led>().unwrap();
-            actual_outputs.push(led.value);
-        }
-
         // Process result
+        let actual_outputs = harness.outputs();
+        let hidden = requirements.hidden_rows.contains(&row);
+        if requirements.diagnostics.is_some() && !hidden {
+            visible_rows.push(row);
+            expected_rows.push(expected_outputs.clone());
+            actual_rows.push(actual_outputs.clone());
+        }
         if expected_outputs != &actual_outputs {
+            if !hidden {
+                first_incorrect.get_or_insert(row);
+            }
+            if failed_rows.last() != Some(&row) {
+                failed_rows.push(row);
+            }
+            if hidden {
+                report.errors.push(ValidationError::IncorrectHiddenOutputs { row });
+            } else {
+                report.errors.push(ValidationError::IncorrectOutputs {
+                    input: inputs.clone(),
+                    expected: expected_outputs.clone(),
+                    actual: actual_outputs,
+                });
+            }
+        }
+    }
+
+    if let (Some(limit), Some((measured, row))) = (requirements.max_delay, slowest) {
+        if measured > limit {
+            let input = if requirements.hidden_rows.contains(&row) { Vec::new() } else { requirements.truth_table.inputs[row].clone() };
+            report.errors.push(ValidationError::TooSlow { measured, limit, input });
+        }
+    }
+
+    if requirements.diagnostics.is_some() {
+        report.hints = diagnostics::diagnose_outputs(&visible_rows, &expected_rows, &actual_rows);
+
+        // Compares the internal nets by running the first failing row through both designs
+        if let (Some(reference), Some(row)) = (diagnostics_reference.as_mut(), first_incorrect) {
+            let ticks = (requirements.max_runtime.unwrap() + 2) as usize;
+            for harness in [&mut *reference, &mut harness] {
+                harness.enable_inspection();
+                harness.apply(&requirements.truth_table.inputs[row]);
+                harness.tick_for(ticks);
+            }
+
+            let nets = diagnostics::differing_nets(reference, &harness).expect("Inspection is enabled above");
+            report.hints.push(Hint::DifferingNets { row, nets });
+        }
+    }
+
+    report.grade(&requirements.grading, &failed_rows, requirements.truth_table.inputs.len(), num_components);
+    report
+}
+
+#[wasm::wasm_bindgen(js_name = "test_equivalence")]
+pub fn js_test_equivalence(component_def: wasm::JsValue, requirements: wasm::JsValue) -> wasm::JsValue {
+    let component_def = component_def.into_serde().expect("Expected the component definition to be in correct format.");
+    let requirements = requirements.into_serde().expect("Expected the equivalence requirements to be in correct format.");
+
+    wasm::JsValue::from_serde(&test_equivalence(component_def, requirements)).unwrap()
+}
+
+#[wasm::wasm_bindgen(js_name = "test_random")]
+pub fn js_test_random(component_def: wasm::JsValue, requirements: wasm::JsValue) -> wasm::JsValue {
+    let component_def = component_def.into_serde().expect("Expected the component definition to be in correct format.");
+    let requirements = requirements.into_serde().expect("Expected the random requirements to be in correct format.");
+
+    wasm::JsValue::from_serde(&test_random(component_def, requirements)).unwrap()
+}
+
+/// Tests the definition on randomly generated input rows against a reference definition or
+/// boolean functions. The seed is recorded in the report, so that the rows can be reproduced.
+pub fn test_random(component_def: ComponentDefinition, requirements: RandomRequirements) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let temp_registry = Arc::new(REGISTRY.with(|reg| reg.lock().clone()));
+
+    // Validate test requirements
+    let (num_inputs, num_outputs) = match &requirements.reference {
+        Reference::Definition(reference) => (reference.pins.input.len(), reference.pins.output.len()),
+        Reference::Expressions(expressions) => (component_def.pins.input.len(), expressions.len()),
+    };
+    let (mut reference, expressions) = match requirements.reference {
+        Reference::Definition(reference) => match TestHarness::new(&temp_registry, *reference) {
+            Ok(reference) => (Some(reference), Vec::new()),
+            Err(e) => {
+                let reason = format!("Reference definition cannot be simulated: {}", describe_error(&e));
+                report.errors.push(ValidationError::InvalidRequirements { reason });
+                return report;
+            },
+        },
+        Reference::Expressions(expressions) => match parse_expressions(&expressions, &component_def.pins.input) {
+            Ok(expressions) => (None, expressions),
+            Err(reason) => {
+                report.errors.push(ValidationError::InvalidRequirements { reason });
+                return report;
+            },
+        },
+    };
+
+    validate_definition(&mut report, &temp_registry, &component_def, requirements.max_components, &requirements.constraints, num_inputs, num_outputs);
+    if report.failure() {
+        return report;
+    }
+
+    let input_names = component_def.pins.input.clone();
+    let num_components = component_def.circuit.as_ref().unwrap().components.len() as u32;
+    let Some(mut harness) = definition_harness(&mut report, &temp_registry, component_def) else {
+        return report;
+    };
+
+    let seed = requirements.seed.unwrap_or_else(Rng::random_seed);
+    let mut rng = Rng::new(seed);
+    report.seed = Some(seed);
+
+    let mut failed_rows = Vec::new();
+    for row in 0..requirements.count {
+        let inputs: Vec<bool> = (0..num_inputs).map(|_| rng.next_bool()).collect();
+        let expected_outputs = if let Some(reference) = reference.as_mut() {
+            run_row(reference, &inputs, requirements.max_runtime)
+        } else {
+            let variables = input_names.iter().map(|x| x.as_str()).zip(inputs.iter().copied()).collect();
+            expressions.iter()
+                .map(|x| x.get_value_from_variables(&variables).expect("Boolean functions only reference inputs"))
+                .collect()
+        };
+
+        let actual_outputs = run_row(&mut harness, &inputs, requirements.max_runtime);
+        if expected_outputs != actual_outputs {
+            failed_rows.push(row);
             report.errors.push(ValidationError::IncorrectOutputs {
-                input: inputs.clone(),
-                expected: expected_outputs.clone(),
+                input: inputs,
+                expected: expected_outputs,
                 actual: actual_outputs,
             });
         }
+    }
+
+    report.grade(&Grading::default(), &failed_rows, requirements.count, num_components);
+    report
+}
+
+/// Builds the test harness of a registered reference definition, describing why it cannot be
+/// used otherwise.
+fn reference_harness(registry: &Arc<Registry>, reference: i32) -> Result<TestHarness, String> {
+    let def = registry.get_definition(reference).map_err(|_| format!("Reference definition {} is not registered", reference))?;
+    TestHarness::new(registry, def.clone()).map_err(|e| format!("Reference definition cannot be simulated: {}", describe_error(&e)))
+}
+
+/// Parses the boolean functions, which may only reference the definition's inputs.
+fn parse_expressions(expressions: &[String], inputs: &[String]) -> Result<Vec<rustlogic::LogicNode>, String> {
+    expressions.iter()
+        .map(|x| {
+            let parsed = rustlogic::parse(x).map_err(|pos| format!("Boolean function '{}' is not valid at position {}", x, pos))?;
+            match parsed.get_variables().into_iter().find(|variable| !inputs.contains(variable)) {
+                Some(variable) => Err(format!("Boolean function '{}' references '{}', which is not an input", x, variable)),
+                None => Ok(parsed),
+            }
+        })
+        .collect()
+}
+
+/// Describes the error together with the errors it wraps, which only describe themselves.
+fn describe_error(e: &dyn std::error::Error) -> String {
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(" {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+/// Builds the harness for the tested definition, pushing an error if it cannot be simulated.
+fn definition_harness(report: &mut ValidationReport, registry: &Arc<Registry>, component_def: ComponentDefinition) -> Option<TestHarness> {
+    match TestHarness::new(registry, component_def) {
+        Ok(harness) => Some(harness),
+        Err(e) => {
+            report.errors.push(ValidationError::ConversionFailed { reason: describe_error(&e) });
+            None
+        },
+    }
+}
+
+/// Simulates a row for the number of ticks, or until the circuit settles.
+fn run_row(harness: &mut TestHarness, inputs: &[bool], max_runtime: Option<u32>) -> Vec<bool> {
+    harness.apply(inputs);
+    match max_runtime {
+        Some(max_runtime) => harness.tick_for((max_runtime + 2) as usize),
+        None => {
+            harness.settle(MAX_SETTLE_TICKS);
+        },
+    }
+    harness.outputs()
+}
+
+#[wasm::wasm_bindgen(js_name = "test_sequential")]
+pub fn js_test_sequential(component_def: wasm::JsValue, requirements: wasm::JsValue) -> wasm::JsValue {
+    let component_def = component_def.into_serde().expect("Expected the component definition to be in correct format.");
+    let requirements = requirements.into_serde().expect("Expected the sequential requirements to be in correct format.");
+
+    wasm::JsValue::from_serde(&test_sequential(component_def, requirements)).unwrap()
+}
+
+/// Tests a definition with state, e.g. containing latches or clocks, by running the steps of a
+/// script against it.
+///
+/// All inputs start low. With a reset, they are held low for its number of ticks, after which
+/// the reset input is held active and then released for as many ticks each.
+pub fn test_sequential(component_def: ComponentDefinition, requirements: SequentialRequirements) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    // The interface is given by the steps, if they set all inputs or expect all outputs
+    let num_inputs = requirements.steps.iter()
+        .find_map(|x| match x { Step::SetInputs { values } => Some(values.len()), _ => None })
+        .unwrap_or(component_def.pins.input.len());
+    let num_outputs = requirements.steps.iter()
+        .find_map(|x| match x { Step::ExpectOutputs { values } => Some(values.len()), _ => None })
+        .unwrap_or(component_def.pins.output.len());
+
+    // Validate test requirements
+    if let Err(reason) = check_steps(&requirements.steps, requirements.reset.as_ref(), num_inputs, Some(num_outputs)) {
+        report.errors.push(ValidationError::InvalidRequirements { reason });
+        return report;
+    }
+
+    let temp_registry = Arc::new(REGISTRY.with(|reg| reg.lock().clone()));
+
+    validate_definition(&mut report, &temp_registry, &component_def, requirements.max_components, &requirements.constraints, num_inputs, num_outputs);
+    if report.failure() {
+        return report;
+    }
+
+    let num_components = component_def.circuit.as_ref().unwrap().components.len() as u32;
+    let Some(mut harness) = definition_harness(&mut report, &temp_registry, component_def) else {
+        return report;
+    };
+    let outputs = run_steps(&mut harness, num_inputs, requirements.reset.as_ref(), &requirements.steps);
+
+    let mut failed_steps = Vec::new();
+    let num_expectations = outputs.len();
+    let expectations = requirements.steps.into_iter().enumerate()
+        .filter_map(|(i, x)| match x { Step::ExpectOutputs { values } => Some((i, values)), _ => None });
+    for ((i, expected), actual) in expectations.zip(outputs) {
+        let matches = expected.iter().zip(actual.iter()).all(|(expected, actual)| expected.is_none_or(|x| x == *actual));
+        if !matches {
+            failed_steps.push(i);
+            report.errors.push(ValidationError::IncorrectStepOutputs { step: i, expected, actual });
+        }
+    }
+
+    report.grade(&requirements.grading, &failed_steps, num_expectations, num_components);
+    report
+}
+
+/// Checks that the steps and reset fit the number of inputs, and the number of outputs if the
+/// steps expect values for them.
+fn check_steps(steps: &[Step], reset: Option<&Reset>, num_inputs: usize, num_outputs: Option<usize>) -> Result<(), String> {
+    for (i, step) in steps.iter().enumerate() {
+        match step {
+            Step::SetInputs { values } if values.len() != num_inputs => {
+                return Err(format!("Step {} sets {} inputs instead of {}", i, values.len(), num_inputs));
+            },
+            Step::ExpectOutputs { values } if num_outputs.is_some_and(|x| values.len() != x) => {
+                return Err(format!("Step {} expects {} outputs instead of {}", i, values.len(), num_outputs.unwrap()));
+            },
+            Step::PulseClock { input, .. } if *input >= num_inputs => {
+                return Err(format!("Step {} pulses input {}, which is out of range", i, input));
+            },
+            _ => {},
+        }
+    }
+
+    match reset {
+        Some(reset) if reset.input >= num_inputs => Err(format!("Reset input {} is out of range", reset.input)),
+        _ => Ok(()),
+    }
+}
+
+/// Runs the steps from all inputs being low, after the reset if there is one.
+///
+/// # Returns
+/// The outputs at each `ExpectOutputs` step.
+fn run_steps(harness: &mut TestHarness, num_inputs: usize, reset: Option<&Reset>, steps: &[Step]) -> Vec<Vec<bool>> {
+    harness.apply(&vec![false; num_inputs]);
+
+    if let Some(reset) = reset {
+        harness.tick_for(reset.ticks as usize);
+        harness.set_input(reset.input, !reset.active_low);
+        harness.tick_for(reset.ticks as usize);
+        harness.set_input(reset.input, reset.active_low);
+        harness.tick_for(reset.ticks as usize);
+    }
+
+    let mut outputs = Vec::new();
+    for step in steps {
+        match *step {
+            Step::SetInputs { ref values } => {
+                for (input, value) in values.iter().enumerate() {
+                    if let Some(value) = *value {
+                        harness.set_input(input, value);
+                    }
+                }
+            },
+            Step::PulseClock { input, ticks } => {
+                harness.set_input(input, true);
+                harness.tick_for(ticks as usize);
+                harness.set_input(input, false);
+                harness.tick_for(ticks as usize);
+            },
+            Step::Wait { ticks } => harness.tick_for(ticks as usize),
+            Step::ExpectOutputs { .. } => outputs.push(harness.outputs()),
+        }
+    }
+    outputs
+}
+
+#[wasm::wasm_bindgen(js_name = "test_reference")]
+pub fn js_test_reference(component_def: wasm::JsValue, requirements: wasm::JsValue) -> wasm::JsValue {
+    let component_def = component_def.into_serde().expect("Expected the component definition to be in correct format.");
+    let requirements = requirements.into_serde().expect("Expected the reference requirements to be in correct format.");
+
+    wasm::JsValue::from_serde(&test_reference(component_def, requirements)).unwrap()
+}
+
+/// Tests that the definition behaves like a registered reference definition, by simulating both
+/// with the same stimulus and comparing their outputs.
+pub fn test_reference(component_def: ComponentDefinition, requirements: ReferenceRequirements) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let temp_registry = Arc::new(REGISTRY.with(|reg| reg.lock().clone()));
+
+    // Validate test requirements
+    let mut reference = match reference_harness(&temp_registry, requirements.reference) {
+        Ok(reference) => reference,
+        Err(reason) => {
+            report.errors.push(ValidationError::InvalidRequirements { reason });
+            return report;
+        },
+    };
+    let (num_inputs, num_outputs) = (reference.num_inputs(), reference.num_outputs());
+    let checked = match &requirements.stimulus {
+        Stimulus::Exhaustive if num_inputs > MAX_TABLE_INPUTS => Err(format!("Reference has too many inputs to enumerate ({})", num_inputs)),
+        Stimulus::Steps { reset, steps } => check_steps(steps, reset.as_ref(), num_inputs, None),
+        _ => Ok(()),
+    };
+    if let Err(reason) = checked {
+        report.errors.push(ValidationError::InvalidRequirements { reason });
+        return report;
+    }
+
+    validate_definition(&mut report, &temp_registry, &component_def, requirements.max_components, &requirements.constraints, num_inputs, num_outputs);
+    if report.failure() {
+        return report;
+    }
+
+    let num_components = component_def.circuit.as_ref().unwrap().components.len() as u32;
+    let Some(mut harness) = definition_harness(&mut report, &temp_registry, component_def) else {
+        return report;
+    };
+
+    let mut failed_cases = Vec::new();
+    let num_cases;
+    if let Stimulus::Steps { reset, steps } = &requirements.stimulus {
+        let expected = run_steps(&mut reference, num_inputs, reset.as_ref(), steps);
+        let actual = run_steps(&mut harness, num_inputs, reset.as_ref(), steps);
+        num_cases = expected.len();
+
+        let expectations = steps.iter().enumerate().filter(|(_, x)| matches!(x, Step::ExpectOutputs { .. }));
+        for (((i, _), expected), actual) in expectations.zip(expected).zip(actual) {
+            if expected != actual {
+                failed_cases.push(i);
+                report.errors.push(ValidationError::IncorrectStepOutputs {
+                    step: i,
+                    expected: expected.into_iter().map(Some).collect(),
+                    actual,
+                });
+            }
+        }
+    } else {
+        let rows: Vec<Vec<bool>> = match requirements.stimulus {
+            Stimulus::Random { count, seed } => {
+                let seed = seed.unwrap_or_else(Rng::random_seed);
+                let mut rng = Rng::new(seed);
+                report.seed = Some(seed);
+                (0..count).map(|_| (0..num_inputs).map(|_| rng.next_bool()).collect()).collect()
+            },
+            _ => (0..1usize << num_inputs)
+                .map(|row| (0..num_inputs).map(|i| row & (1 << (num_inputs - 1 - i)) != 0).collect())
+                .collect(),
+        };
+        num_cases = rows.len();
+
+        for (row, inputs) in rows.into_iter().enumerate() {
+            let expected_outputs = run_row(&mut reference, &inputs, requirements.max_runtime);
+            let actual_outputs = run_row(&mut harness, &inputs, requirements.max_runtime);
+            if expected_outputs != actual_outputs {
+                failed_cases.push(row);
+                report.errors.push(ValidationError::IncorrectOutputs {
+                    input: inputs,
+                    expected: expected_outputs,
+                    actual: actual_outputs,
+                });
+            }
+        }
+    }
+
+    report.grade(&requirements.grading, &failed_cases, num_cases, num_components);
+    report
+}
+
+#[wasm::wasm_bindgen(js_name = "test_fsm")]
+pub fn js_test_fsm(component_def: wasm::JsValue, requirements: wasm::JsValue) -> wasm::JsValue {
+    let component_def = component_def.into_serde().expect("Expected the component definition to be in correct format.");
+    let requirements = requirements.into_serde().expect("Expected the state machine requirements to be in correct format.");
+
+    wasm::JsValue::from_serde(&test_fsm(component_def, requirements)).unwrap()
+}
+
+/// Generates the truth table of a transparent definition by simulating every input combination.
+#[wasm::wasm_bindgen]
+pub fn generate_truth_table(component_def: wasm::JsValue) -> Result<wasm::JsValue, String> {
+    let component_def: ComponentDefinition = component_def.into_serde().expect("Expected the component definition to be in correct format.");
+    let truth_table = REGISTRY.with(|reg| TruthTable::from_definition(&reg.lock(), &component_def)).map_err(|e| e.to_string())?;
+
+    Ok(wasm::JsValue::from_serde(&truth_table).unwrap())
+}
+
+/// Tests that the component definition computes the same function as the reference definition
+/// for every input, instead of only the rows of a truth table.
+pub fn test_equivalence(component_def: ComponentDefinition, requirements: EquivalenceRequirements) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let reference = &requirements.reference;
+    let mut temp_registry = Registry::default();
+    REGISTRY.with(|reg| temp_registry = reg.lock().clone());
+
+    validate_definition(&mut report, &temp_registry, &component_def, requirements.max_components, &requirements.constraints, reference.pins.input.len(), reference.pins.output.len());
+    if report.failure() {
+        return report;
+    }
 
-        // Reset simulation state
-        ctx.reset();
+    match check_equivalence(&temp_registry, reference, &component_def) {
+        Ok(None) => {},
+        Ok(Some(counterexample)) => report.errors.push(ValidationError::IncorrectOutputs {
+            input: counterexample.input,
+            expected: counterexample.expected,
+            actual: counterexample.actual,
+        }),
+        Err(e) => report.errors.push(ValidationError::AnalysisFailed { reason: e.to_string() }),
     }
 
+    report.grade_pass_fail();
     report
 }
 
+/// Validates the component definition's size and interface, capturing all related errors.
+fn validate_definition(report: &mut ValidationReport, registry: &Registry, component_def: &ComponentDefinition, max_components: Option<u32>, constraints: &Constraints, num_inputs: usize, num_outputs: usize) {
+    let Some(circuit) = component_def.circuit.as_ref() else {
+        report.errors.push(ValidationError::ConversionFailed { reason: IncorrectKind.to_string() });
+        return;
+    };
+
+    let used = circuit.components.len() as u32;
+    let max_allowed = max_components.unwrap_or(u32::MAX);
+    if !(used <= max_allowed) {
+        report.errors.push(ValidationError::MaxComponentsExceeded { used, max_allowed });
+    }
+
+    if !(component_def.pins.input.len() == num_inputs) {
+        report.errors.push(ValidationError::InvalidComponentInterface { 
+            kind: ConnectorKind::Input,
+            expected: num_inputs as u32, 
+            actual: component_def.pins.input.len() as u32,
+        });
+    } else if !(component_def.pins.output.len() == num_outputs) {
+        report.errors.push(ValidationError::InvalidComponentInterface { 
+            kind: ConnectorKind::Output,
+            expected: num_outputs as u32, 
+            actual: component_def.pins.output.len() as u32,
+        });
+    }
+
+    constraints::check_constraints(report, registry, component_def, constraints);
+}
+
 /// Convert a Transparent component definition into a test circuit definition.
-fn to_test_circuit_definition(registry: &mut Registry, mut component_def: ComponentDefinition) -> Result<CircuitDefinition, ConversionError> {
+pub(crate) fn to_test_circuit_definition(registry: &mut Registry, mut component_def: ComponentDefinition) -> Result<CircuitDefinition, ConversionError> {
     rassert!(component_def.kind == ComponentKind::Transparent, IncorrectKind);
     component_def.id = i32::MIN; // Reserved for temporary definitions
 
@@ -153,5 +646,455 @@ fn to_test_circuit_definition(registry: &mut Registry, mut component_def: Compon
 pub enum ConversionError {
     #[error("Cannot convert non-transparent component definition to a circuit definition.")]
     IncorrectKind,
+
+    #[error("Cannot enumerate {0} inputs, at most {} are supported.", crate::component::definition::MAX_TABLE_INPUTS)]
+    TooManyInputs(usize),
+
+    #[error("Failed to build the test circuit.")]
+    DefinitionError(#[from] DefinitionError),
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::InspectionError;
+    use crate::circuit::registry::NAND_ID;
+    use crate::component::definition::{Pins, Signal, TransparentBuilder};
+
+    /// Builds a D latch out of NAND gates, returning its output.
+    fn d_latch(builder: &mut TransparentBuilder, d: Signal, enable: Signal) -> Signal {
+        let (q, q_inverted) = (builder.net(), builder.net());
+        let d_inverted = builder.not(d);
+        let set = builder.nand(d, enable);
+        let reset = builder.nand(d_inverted, enable);
+        let q_driver = builder.nand(set, q_inverted);
+        let q_inverted_driver = builder.nand(reset, q);
+        builder.drive(q, q_driver);
+        builder.drive(q_inverted, q_inverted_driver);
+        q
+    }
+
+    #[test]
+    fn flip_flop_steps() {
+        // A master-slave D flip-flop, taking D on the rising edge of C
+        let mut builder = TransparentBuilder::default();
+        let clock_inverted = builder.not(Signal::Input(1));
+        let master = d_latch(&mut builder, Signal::Input(0), clock_inverted);
+        let slave = d_latch(&mut builder, master, Signal::Input(1));
+        let pins = Pins { input: vec!["D".into(), "C".into()], output: vec!["Q".into()] };
+        let def = builder.build(1, "FlipFlop".into(), pins, &[slave]);
+
+        let requirements: SequentialRequirements = serde_json::from_value(serde_json::json!({
+            "reset": { "input": 1, "ticks": 20 },
+            "steps": [
+                { "type": "expectOutputs", "values": [false] },
+                { "type": "setInputs", "values": [true, null] },
+                { "type": "wait", "ticks": 20 },
+                { "type": "expectOutputs", "values": [false] },
+                { "type": "pulseClock", "input": 1, "ticks": 20 },
+                { "type": "expectOutputs", "values": [true] },
+                { "type": "setInputs", "values": [false, null] },
+                { "type": "wait", "ticks": 20 },
+                { "type": "expectOutputs", "values": [false] },
+            ],
+        })).unwrap();
+
+        let report = test_sequential(def.clone(), requirements);
+        assert_eq!(report.errors, vec![ValidationError::IncorrectStepOutputs {
+            step: 8,
+            expected: vec![Some(false)],
+            actual: vec![true],
+        }]);
+
+        // Steps which do not fit the interface are reported instead of run
+        let requirements: SequentialRequirements = serde_json::from_value(serde_json::json!({
+            "steps": [{ "type": "pulseClock", "input": 2, "ticks": 20 }],
+        })).unwrap();
+        let report = test_sequential(def, requirements);
+        assert!(matches!(report.errors[..], [ValidationError::InvalidRequirements { .. }]), "{:?}", report);
+    }
+
+    /// Builds a flip-flop with inputs T, R and C, loading `next(T, Q)` on the rising edge of C
+    /// unless R is set.
+    fn flip_flop(next: impl Fn(&mut TransparentBuilder, Signal, Signal) -> Signal) -> ComponentDefinition {
+        let mut builder = TransparentBuilder::default();
+        let q = builder.net();
+        let next = next(&mut builder, Signal::Input(0), q);
+        let reset_inverted = builder.not(Signal::Input(1));
+        let d = builder.and(&[next, reset_inverted]);
+
+        let clock_inverted = builder.not(Signal::Input(2));
+        let master = d_latch(&mut builder, d, clock_inverted);
+        let slave = d_latch(&mut builder, master, Signal::Input(2));
+        builder.drive(q, slave);
+
+        let pins = Pins { input: vec!["T".into(), "R".into(), "C".into()], output: vec!["Q".into()] };
+        builder.build(1, "FlipFlop".into(), pins, &[slave])
+    }
+
+    #[test]
+    fn toggle_fsm() {
+        let requirements: FsmRequirements = serde_json::from_value(serde_json::json!({
+            "reset": { "input": 1, "ticks": 20 },
+            "clock": 2,
+            "clockTicks": 20,
+            "initialState": "Off",
+            "states": [
+                { "name": "Off", "outputs": [false] },
+                { "name": "On", "outputs": [true] },
+            ],
+            "transitions": [
+                { "from": "Off", "inputs": [false, null, null], "to": "Off" },
+                { "from": "Off", "inputs": [true, null, null], "to": "On" },
+                { "from": "On", "inputs": [false, null, null], "to": "On" },
+                { "from": "On", "inputs": [true, null, null], "to": "Off" },
+            ],
+        })).unwrap();
+
+        let toggle = flip_flop(|builder, t, q| builder.xor(&[t, q]));
+        assert_eq!(test_fsm(toggle, requirements.clone()).errors, vec![]);
+
+        // Loads T instead of toggling, so it stays on while T is set and turns off otherwise
+        let load = flip_flop(|_, t, _| t);
+        assert_eq!(test_fsm(load, requirements).errors, vec![
+            ValidationError::WrongTransition {
+                from: "On".into(),
+                input: vec![false, false, false],
+                expected: "On".into(),
+                actual: Some("Off".into()),
+            },
+            ValidationError::MissingTransition {
+                from: "On".into(),
+                input: vec![true, false, false],
+                expected: "Off".into(),
+            },
+        ]);
+    }
+
+    #[test]
+    fn harness_inspection() {
+        let toggle = flip_flop(|builder, t, q| builder.xor(&[t, q]));
+        let mut harness = TestHarness::new(&Arc::new(Registry::default()), toggle).unwrap();
+        harness.apply(&[false, true, false]);
+        harness.tick_for(20);
+        assert!(matches!(harness.latch_values(), Err(InspectionError::NotEnabled)));
+        assert!(matches!(harness.inner_state(), Err(InspectionError::NotEnabled)));
+
+        harness.enable_inspection();
+        harness.apply(&[false, true, false]);
+        harness.tick_for(20);
+        assert!(!harness.latch_values().unwrap().is_empty());
+        assert!(!harness.inner_state().unwrap().is_empty());
+    }
+
+    #[test]
+    fn weighted_groups() {
+        let def: ComponentDefinition = serde_json::from_str(include_str!("../../tests/assets/and_gate_definition.json")).unwrap();
+
+        // Expects an OR gate instead, so only the rows with equal inputs pass
+        let requirements: CombinationalRequirements = serde_json::from_value(serde_json::json!({
+            "maxRuntime": 16,
+            "truthTable": {
+                "inputs": [[false, false], [false, true], [true, false], [true, true]],
+                "outputs": [[false], [true], [true], [true]],
+            },
+            "grading": {
+                "groups": [
+                    { "name": "Equal inputs", "weight": 3.0, "cases": [0, 3] },
+                    { "name": "Mixed inputs", "weight": 1.0, "cases": [1, 2] },
+                ],
+                "componentPenalty": { "target": 1, "perComponent": 0.125, "maxPenalty": 0.5 },
+            },
+        })).unwrap();
+
+        let report = test_combinational(def.clone(), requirements.clone());
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(report.groups, vec![
+            GroupResult { name: "Equal inputs".into(), weight: 3.0, passed: true, failed_cases: vec![] },
+            GroupResult { name: "Mixed inputs".into(), weight: 1.0, passed: false, failed_cases: vec![1, 2] },
+        ]);
+        assert_eq!(report.score, 0.75 - 0.125);
+
+        // Without groups, every row is worth the same
+        let requirements = CombinationalRequirements { grading: Grading::default(), ..requirements };
+        let report = test_combinational(def, requirements);
+        assert!(report.groups.is_empty());
+        assert_eq!(report.score, 0.5);
+    }
+
+    #[test]
+    fn hidden_rows() {
+        let def: ComponentDefinition = serde_json::from_str(include_str!("../../tests/assets/and_gate_definition.json")).unwrap();
+        let requirements: CombinationalRequirements = serde_json::from_value(serde_json::json!({
+            "maxRuntime": 16,
+            "truthTable": {
+                "inputs": [[false, false], [false, true], [true, false], [true, true]],
+                "outputs": [[false], [true], [true], [true]],
+            },
+            "hiddenRows": [2],
+        })).unwrap();
+
+        let report = test_combinational(def, requirements);
+        assert_eq!(report.errors, vec![
+            ValidationError::IncorrectOutputs { input: vec![false, true], expected: vec![true], actual: vec![false] },
+            ValidationError::IncorrectHiddenOutputs { row: 2 },
+        ]);
+        assert_eq!(report.score, 0.5);
+    }
+
+    #[test]
+    fn random_rows() {
+        let def: ComponentDefinition = serde_json::from_str(include_str!("../../tests/assets/and_gate_definition.json")).unwrap();
+        let requirements = |reference| RandomRequirements {
+            max_runtime: None,
+            max_components: None,
+            constraints: Constraints::default(),
+            count: 32,
+            seed: Some(42),
+            reference,
+        };
+
+        let report = test_random(def.clone(), requirements(Reference::Expressions(vec!["[A]&[B]".into()])));
+        assert!(report.success(), "{:?}", report);
+        assert_eq!(report.seed, Some(42));
+
+        // The same seed reproduces the same rows
+        let reference = Reference::Expressions(vec!["[A]|[B]".into()]);
+        let report = test_random(def.clone(), requirements(reference.clone()));
+        assert!(report.failure());
+        assert_eq!(report.errors, test_random(def.clone(), requirements(reference)).errors);
+        assert!(report.errors.iter().all(|x| matches!(x, ValidationError::IncorrectOutputs { input, .. } if input[0] != input[1])));
+
+        // A definition as the reference
+        let report = test_random(def.clone(), requirements(Reference::Definition(Box::new(def.clone()))));
+        assert!(report.success(), "{:?}", report);
+
+        // Malformed functions are reported instead of run
+        for expression in ["[A]&", "[A]&[C]"] {
+            let report = test_random(def.clone(), requirements(Reference::Expressions(vec![expression.into()])));
+            assert!(matches!(report.errors[..], [ValidationError::InvalidRequirements { .. }]), "{:?}", report);
+        }
+    }
+
+    #[test]
+    fn propagation_delay() {
+        let def: ComponentDefinition = serde_json::from_str(include_str!("../../tests/assets/and_gate_definition.json")).unwrap();
+        let requirements = |max_delay| serde_json::from_value::<CombinationalRequirements>(serde_json::json!({
+            "maxRuntime": 16,
+            "maxDelay": max_delay,
+            "truthTable": {
+                "inputs": [[false, false], [false, true], [true, false], [true, true]],
+                "outputs": [[false], [false], [false], [true]],
+            },
+        })).unwrap();
+
+        // The switches, both NAND gates and the LED each take a tick
+        let report = test_combinational(def.clone(), requirements(5));
+        assert!(report.success(), "{:?}", report);
+
+        let report = test_combinational(def, requirements(4));
+        assert_eq!(report.errors, vec![ValidationError::TooSlow { measured: 5, limit: 4, input: vec![false, false] }]);
+        assert_eq!(report.score, 0.0);
+    }
+
+    #[test]
+    fn diagnostic_hints() {
+        let and: ComponentDefinition = serde_json::from_str(include_str!("../../tests/assets/and_gate_definition.json")).unwrap();
+        let mut reference = and.clone();
+        reference.id = 60;
+        REGISTRY.with(|reg| reg.lock().insert(reference));
+
+        let requirements: CombinationalRequirements = serde_json::from_value(serde_json::json!({
+            "maxRuntime": 16,
+            "truthTable": {
+                "inputs": [[false, false], [false, true], [true, false], [true, true]],
+                "outputs": [[false], [false], [false], [true]],
+            },
+            "diagnostics": { "reference": 60 },
+        })).unwrap();
+
+        // Leaving an input of the inverter unconnected makes it output 1
+        let mut unconnected = and.clone();
+        unconnected.circuit.as_mut().unwrap().connections[0].to.pop();
+        let report = test_combinational(unconnected.clone(), requirements.clone());
+        assert_eq!(report.hints, vec![
+            Hint::WrongOutput { output: 0, rows: vec![0, 1, 2] },
+            Hint::ConstantOutput { output: 0, value: true },
+            Hint::DifferingNets { row: 0, nets: vec![Connector::new(1, 2)] },
+        ]);
+
+        // Hidden rows are left out of the hints
+        let mut hidden = requirements.clone();
+        hidden.hidden_rows = vec![0];
+        let report = test_combinational(unconnected, hidden);
+        assert_eq!(report.hints, vec![
+            Hint::WrongOutput { output: 0, rows: vec![1, 2] },
+            Hint::ConstantOutput { output: 0, value: true },
+            Hint::DifferingNets { row: 1, nets: vec![Connector::new(1, 2)] },
+        ]);
+
+        // Outputs the NAND gate instead of the inverted one
+        let mut inverted = and.clone();
+        inverted.pin_mapping.as_mut().unwrap().output[0] = vec![Connector::new(0, 2)];
+        let report = test_combinational(inverted, requirements.clone());
+        assert_eq!(report.hints[..2], [
+            Hint::WrongOutput { output: 0, rows: vec![0, 1, 2, 3] },
+            Hint::InvertedOutput { output: 0 },
+        ]);
+
+        // An unregistered reference is reported even if nothing fails
+        let mut requirements = requirements;
+        requirements.diagnostics = Some(Diagnostics { reference: Some(999) });
+        let report = test_combinational(and, requirements);
+        assert!(matches!(report.errors[..], [ValidationError::InvalidRequirements { .. }]), "{:?}", report);
+    }
+
+    #[test]
+    fn batch_grading() {
+        let and: ComponentDefinition = serde_json::from_str(include_str!("../../tests/assets/and_gate_definition.json")).unwrap();
+        let mut inverted = and.clone();
+        inverted.pin_mapping.as_mut().unwrap().output[0] = vec![Connector::new(0, 2)];
+
+        // A NAND gate feeding back into itself never settles while A is high
+        let mut builder = TransparentBuilder::default();
+        let q = builder.net();
+        let oscillator = builder.nand(Signal::Input(0), q);
+        builder.drive(q, oscillator);
+        let oscillator = builder.build(1, "Oscillator".into(), and.pins.clone(), &[oscillator]);
+
+        let requirements: CombinationalRequirements = serde_json::from_value(serde_json::json!({
+            "maxRuntime": 1_000_000_000,
+            "truthTable": {
+                "inputs": [[false, false], [false, true], [true, false], [true, true]],
+                "outputs": [[false], [false], [false], [true]],
+            },
+        })).unwrap();
+
+        let submissions = vec![
+            Submission { id: "and".into(), definition: and },
+            Submission { id: "inverted".into(), definition: inverted },
+            Submission { id: "oscillator".into(), definition: oscillator.clone() },
+        ];
+        let reports = grade_batch(Arc::new(Registry::default()), &requirements, submissions.clone(), Some(std::time::Duration::from_millis(500))).unwrap();
+        assert_eq!(reports.len(), 3);
+        assert!(reports["and"].success(), "{:?}", reports["and"]);
+        assert_eq!(reports["inverted"].errors.len(), 4);
+        assert_eq!(reports["oscillator"].errors, vec![ValidationError::TimedOut { limit_ms: 500 }]);
+
+        // Reports are keyed by ID, so a repeated ID would hide one of them
+        let mut duplicated = submissions;
+        duplicated[1].id = "and".into();
+        let result = grade_batch(Arc::new(Registry::default()), &requirements, duplicated, None);
+        assert!(matches!(result, Err(BatchError::DuplicateId(id)) if id == "and"));
+
+        // A cancelled test stops simulating instead of running for the whole runtime
+        let cancel = Arc::new(AtomicBool::new(true));
+        let report = test_combinational_in(&Arc::new(Registry::default()), oscillator, &requirements, Some(cancel));
+        assert!(report.errors.is_empty(), "{:?}", report);
+    }
+
+    #[test]
+    fn flattened_constraints() {
+        let and: ComponentDefinition = serde_json::from_str(include_str!("../../tests/assets/and_gate_definition.json")).unwrap();
+        let mut sub_def = and.clone();
+        sub_def.id = 50;
+        REGISTRY.with(|reg| reg.lock().insert(sub_def));
+
+        // Replaces both NAND gates of the AND gate with AND gates, hiding four NAND gates
+        let mut def = and.clone();
+        def.circuit.as_mut().unwrap().components.iter_mut().for_each(|x| x.def_id = 50);
+
+        let requirements: CombinationalRequirements = serde_json::from_value(serde_json::json!({
+            "maxRuntime": 16,
+            "maxComponents": 2,
+            "constraints": { "maxBuiltins": { "-1": 3, "-2": 0 }, "forbiddenDefinitions": [50], "maxDepth": 1 },
+            "truthTable": {
+                "inputs": [[false, false], [false, true], [true, false], [true, true]],
+                "outputs": [[false], [false], [false], [true]],
+            },
+        })).unwrap();
+
+        let report = test_combinational(def, requirements.clone());
+        assert_eq!(report.errors, vec![
+            ValidationError::MaxBuiltinsExceeded { def_id: -1, used: 4, max_allowed: 3 },
+            ValidationError::ForbiddenDefinition { def_id: 50 },
+            ValidationError::MaxDepthExceeded { depth: 2, max_allowed: 1 },
+        ]);
+
+        let report = test_combinational(and, requirements);
+        assert!(report.success(), "{:?}", report);
+    }
+
+    #[test]
+    fn reference_definitions() {
+        let mut and: ComponentDefinition = serde_json::from_str(include_str!("../../tests/assets/and_gate_definition.json")).unwrap();
+        and.id = 42;
+        REGISTRY.with(|reg| reg.lock().insert(and.clone()));
+
+        let requirements: ReferenceRequirements = serde_json::from_value(serde_json::json!({
+            "maxRuntime": 16,
+            "reference": 42,
+            "stimulus": { "type": "exhaustive" },
+        })).unwrap();
+        let report = test_reference(and.clone(), requirements.clone());
+        assert!(report.success(), "{:?}", report);
+
+        let mut builder = TransparentBuilder::default();
+        let nand = builder.nand(Signal::Input(0), Signal::Input(1));
+        let nand = builder.build(1, "Nand".into(), and.pins.clone(), &[nand]);
+        let report = test_reference(nand, requirements);
+        assert_eq!(report.errors.len(), 4);
+        assert_eq!(report.score, 0.0);
+
+        // A D latch passes D through while C is high, unlike the flip-flop it is compared to
+        let mut builder = TransparentBuilder::default();
+        let clock_inverted = builder.not(Signal::Input(1));
+        let master = d_latch(&mut builder, Signal::Input(0), clock_inverted);
+        let slave = d_latch(&mut builder, master, Signal::Input(1));
+        let pins = Pins { input: vec!["D".into(), "C".into()], output: vec!["Q".into()] };
+        let flip_flop = builder.build(43, "FlipFlop".into(), pins.clone(), &[slave]);
+        REGISTRY.with(|reg| reg.lock().insert(flip_flop));
+
+        let mut builder = TransparentBuilder::default();
+        let q = d_latch(&mut builder, Signal::Input(0), Signal::Input(1));
+        let latch = builder.build(1, "Latch".into(), pins, &[q]);
+
+        let requirements: ReferenceRequirements = serde_json::from_value(serde_json::json!({
+            "reference": 43,
+            "stimulus": {
+                "type": "steps",
+                "steps": [
+                    { "type": "setInputs", "values": [true, false] },
+                    { "type": "wait", "ticks": 20 },
+                    { "type": "setInputs", "values": [true, true] },
+                    { "type": "wait", "ticks": 20 },
+                    { "type": "expectOutputs" },
+                    { "type": "setInputs", "values": [false, true] },
+                    { "type": "wait", "ticks": 20 },
+                    { "type": "expectOutputs" },
+                ],
+            },
+        })).unwrap();
+        let report = test_reference(latch.clone(), requirements);
+        assert_eq!(report.errors, vec![
+            ValidationError::IncorrectStepOutputs { step: 7, expected: vec![Some(true)], actual: vec![false] },
+        ]);
+        assert_eq!(report.score, 0.5);
+
+        // Unregistered and builtin references cannot be simulated, and neither can the steps
+        // pulsing an input the reference does not have
+        for (reference, stimulus) in [
+            (999, serde_json::json!({ "type": "exhaustive" })),
+            (NAND_ID, serde_json::json!({ "type": "exhaustive" })),
+            (43, serde_json::json!({ "type": "steps", "steps": [{ "type": "pulseClock", "input": 2, "ticks": 20 }] })),
+        ] {
+            let requirements: ReferenceRequirements = serde_json::from_value(serde_json::json!({
+                "reference": reference,
+                "stimulus": stimulus,
+            })).unwrap();
+            let report = test_reference(latch.clone(), requirements);
+            assert!(matches!(report.errors[..], [ValidationError::InvalidRequirements { .. }]), "{:?}", report);
+        }
+    }
+}
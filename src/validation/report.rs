@@ -1,3 +1,6 @@
+use crate::circuit::Connector;
+use super::Grading;
+
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum ConnectorKind {
@@ -14,20 +17,139 @@ pub enum ValidationError {
         expected: Vec<bool>,
         actual: Vec<bool>,
     },
+    /// A hidden row of the truth table failed, which is reported without revealing it.
+    IncorrectHiddenOutputs {
+        row: usize,
+    },
     MaxComponentsExceeded {
         used: u32,
         max_allowed: u32,
     },
+    /// More instances of a builtin were used than allowed, counted through all sub-definitions.
+    MaxBuiltinsExceeded {
+        def_id: i32,
+        used: u32,
+        max_allowed: u32,
+    },
+    /// A forbidden definition was used somewhere in the hierarchy.
+    ForbiddenDefinition {
+        def_id: i32,
+    },
+    /// Sub-definitions were nested deeper than allowed.
+    MaxDepthExceeded {
+        depth: u32,
+        max_allowed: u32,
+    },
     InvalidComponentInterface {
         kind: ConnectorKind,
         expected: u32,
         actual: u32,
     },
+    /// The slowest row took longer to settle than allowed. The input is empty if the row is
+    /// hidden.
+    TooSlow {
+        measured: u32,
+        limit: u32,
+        input: Vec<bool>,
+    },
+    /// Outputs did not match at a step of a sequential test.
+    IncorrectStepOutputs {
+        step: usize,
+        expected: Vec<Option<bool>>,
+        actual: Vec<bool>,
+    },
+    /// Outputs did not match in a state of a state machine.
+    IncorrectStateOutputs {
+        state: String,
+        expected: Vec<Option<bool>>,
+        actual: Vec<bool>,
+    },
+    /// A transition of a state machine led to the wrong state, or to an unknown one if `actual`
+    /// is `None`.
+    WrongTransition {
+        from: String,
+        input: Vec<bool>,
+        expected: String,
+        actual: Option<String>,
+    },
+    /// A transition of a state machine did not change the circuit's state.
+    MissingTransition {
+        from: String,
+        input: Vec<bool>,
+        expected: String,
+    },
+    /// A state of a state machine was never reached through correct transitions.
+    UnreachedState {
+        state: String,
+    },
+    /// The submission was still running when its batch stopped waiting for it.
+    TimedOut {
+        limit_ms: u64,
+    },
+    /// The definition could not be analyzed, e.g. because it is not combinational.
+    AnalysisFailed {
+        reason: String,
+    },
+    /// The definition cannot be simulated, e.g. because it is not transparent.
+    ConversionFailed {
+        reason: String,
+    },
+    /// The requirements cannot be tested against, e.g. because a boolean function does not parse.
+    InvalidRequirements {
+        reason: String,
+    },
+}
+
+/// Structured hint explaining why test cases failed.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(tag = "type", content = "data")]
+pub enum Hint {
+    /// An output was wrong in the rows.
+    WrongOutput {
+        output: usize,
+        rows: Vec<usize>,
+    },
+    /// An output had the same value in every row while the expected one changes, which suggests
+    /// an unconnected input.
+    ConstantOutput {
+        output: usize,
+        value: bool,
+    },
+    /// An output was the inverse of the expected one in every row.
+    InvertedOutput {
+        output: usize,
+    },
+    /// Internal nets whose values differ from those of the reference design at the row, keyed
+    /// by their driving connectors.
+    DifferingNets {
+        row: usize,
+        nets: Vec<Connector>,
+    },
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize)]
+/// The result of a group of test cases.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupResult {
+    pub name: String,
+    pub weight: f64,
+    pub passed: bool,
+    /// Indices of the failed cases in the group.
+    pub failed_cases: Vec<usize>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize)]
 pub struct ValidationReport {
     pub errors: Vec<ValidationError>,
+    /// The grade between 0 and 1.
+    pub score: f64,
+    pub groups: Vec<GroupResult>,
+    /// Seed of the randomly generated test cases, to reproduce them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hints: Vec<Hint>,
 }
 
 impl ValidationReport {
@@ -42,5 +164,41 @@ impl ValidationReport {
     pub fn failure(&self) -> bool {
         !self.success()
     }
+
+    /// Scores the report as either passed or failed.
+    pub(crate) fn grade_pass_fail(&mut self) {
+        self.score = if self.success() { 1.0 } else { 0.0 };
+    }
+
+    /// Scores the report from the failed test cases, out of `num_cases` cases.
+    ///
+    /// Without groups, every case is worth the same. Cases outside of all groups do not count
+    /// towards the score otherwise.
+    pub(crate) fn grade(&mut self, grading: &Grading, failed: &[usize], num_cases: usize, num_components: u32) {
+        self.groups = grading.groups.iter()
+            .map(|group| {
+                let failed_cases: Vec<usize> = group.cases.iter().copied().filter(|x| failed.contains(x)).collect();
+                GroupResult {
+                    name: group.name.clone(),
+                    weight: group.weight,
+                    passed: failed_cases.is_empty(),
+                    failed_cases,
+                }
+            })
+            .collect();
+
+        let score = if self.groups.is_empty() {
+            if num_cases == 0 { 1.0 } else { 1.0 - failed.len() as f64 / num_cases as f64 }
+        } else {
+            let total: f64 = self.groups.iter().map(|x| x.weight).sum();
+            let passed: f64 = self.groups.iter().filter(|x| x.passed).map(|x| x.weight).sum();
+            if total > 0.0 { passed / total } else { 1.0 }
+        };
+
+        let penalty = grading.component_penalty.as_ref()
+            .map(|x| (num_components.saturating_sub(x.target) as f64 * x.per_component).min(x.max_penalty))
+            .unwrap_or(0.0);
+        self.score = (score - penalty).clamp(0.0, 1.0);
+    }
 }
 
@@ -1,10 +1,220 @@
+use std::collections::HashMap;
+
+use crate::component::ComponentDefinition;
 use crate::component::definition::TruthTable;
 
 #[derive(Debug, Clone, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CombinationalRequirements {
     pub max_runtime: Option<u32>,
+    /// The maximum number of ticks any row may take to settle, from applying its inputs until
+    /// no more events are scheduled. Rows that are slower fail.
+    pub max_delay: Option<u32>,
     pub max_components: Option<u32>,
+    #[serde(default)]
+    pub constraints: Constraints,
     pub truth_table: TruthTable,
+    /// Indices of the rows which are graded, but reported without their inputs and outputs.
+    #[serde(default)]
+    pub hidden_rows: Vec<usize>,
+    /// Groups the rows of the truth table.
+    #[serde(default)]
+    pub grading: Grading,
+    /// Adds hints explaining the failing rows to the report.
+    pub diagnostics: Option<Diagnostics>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+    /// ID of a registered reference design, whose internal nets are compared to those of the
+    /// definition at the first failing row. Nets are matched by their local connectors.
+    pub reference: Option<i32>,
+}
+
+/// Limits on the components a definition is built from, which are counted through all of its
+/// transparent sub-definitions, unlike `max_components`.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Constraints {
+    /// The maximum number of each builtin by its definition ID, e.g. `{ "-1": 9, "-2": 0 }`.
+    #[serde(default)]
+    pub max_builtins: HashMap<i32, u32>,
+    /// Definitions which may not be used anywhere in the hierarchy.
+    #[serde(default)]
+    pub forbidden_definitions: Vec<i32>,
+    /// The maximum nesting depth, where a definition made only of builtins has depth 1.
+    pub max_depth: Option<u32>,
+}
+
+/// How partial credit is given for the test cases, e.g. the rows of a truth table.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Grading {
+    #[serde(default)]
+    pub groups: Vec<TestGroup>,
+    pub component_penalty: Option<ComponentPenalty>,
+}
+
+/// A named group of test cases, which is passed only if all of its cases pass.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestGroup {
+    pub name: String,
+    pub weight: f64,
+    /// Indices of the cases in the group.
+    pub cases: Vec<usize>,
+}
+
+/// Lowers the score of definitions using more components than the target.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentPenalty {
+    pub target: u32,
+    /// Subtracted from the score for each component above the target.
+    pub per_component: f64,
+    pub max_penalty: f64,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EquivalenceRequirements {
+    pub max_components: Option<u32>,
+    #[serde(default)]
+    pub constraints: Constraints,
+    /// The definition computing the expected function.
+    pub reference: ComponentDefinition,
+}
+
+/// Checks randomly generated input rows against a reference, for definitions with too many
+/// inputs to list all of their rows.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RandomRequirements {
+    /// Ticks to run each row for, or until the circuit settles if missing.
+    pub max_runtime: Option<u32>,
+    pub max_components: Option<u32>,
+    #[serde(default)]
+    pub constraints: Constraints,
+    /// The number of random rows.
+    pub count: usize,
+    /// Seed of the random rows, chosen randomly if missing.
+    pub seed: Option<u32>,
+    pub reference: Reference,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Reference {
+    /// A definition computing the expected outputs, simulated in the same way.
+    Definition(Box<ComponentDefinition>),
+    /// A boolean function for each output, referencing the input pins as `[A]`.
+    Expressions(Vec<String>),
 }
 
+/// Checks that a definition behaves like a registered reference definition, by running the same
+/// stimulus through both.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceRequirements {
+    /// Ticks to run each row for, or until the circuit settles if missing. Unused by steps.
+    pub max_runtime: Option<u32>,
+    pub max_components: Option<u32>,
+    #[serde(default)]
+    pub constraints: Constraints,
+    /// ID of the definition in the registry computing the expected outputs.
+    pub reference: i32,
+    pub stimulus: Stimulus,
+    /// Groups the rows, or the steps expecting outputs.
+    #[serde(default)]
+    pub grading: Grading,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Stimulus {
+    /// Every input combination, with the first input as the most significant bit.
+    Exhaustive,
+    /// Randomly generated rows, chosen by a random seed if missing.
+    Random { count: usize, seed: Option<u32> },
+    /// Scripted steps, comparing the outputs at each `ExpectOutputs` step, whose values are
+    /// ignored.
+    Steps { reset: Option<Reset>, steps: Vec<Step> },
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SequentialRequirements {
+    pub max_components: Option<u32>,
+    #[serde(default)]
+    pub constraints: Constraints,
+    /// An input held active before the steps run, to bring the circuit into a known state.
+    pub reset: Option<Reset>,
+    pub steps: Vec<Step>,
+    /// Groups the steps expecting outputs.
+    #[serde(default)]
+    pub grading: Grading,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reset {
+    pub input: usize,
+    pub ticks: u32,
+    #[serde(default)]
+    pub active_low: bool,
+}
+
+/// A step of a sequential test, in which inputs and outputs are indexed as the pins of the
+/// tested definition.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Step {
+    /// Sets the inputs, leaving those set to `null` unchanged.
+    SetInputs { values: Vec<Option<bool>> },
+    /// Sets the input high for the number of ticks, then low for the same number of ticks.
+    PulseClock { input: usize, ticks: u32 },
+    Wait { ticks: u32 },
+    /// Compares the outputs, ignoring those expected to be `null`.
+    ExpectOutputs {
+        #[serde(default)]
+        values: Vec<Option<bool>>,
+    },
+}
+
+/// A Moore state machine which a sequential definition has to implement.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsmRequirements {
+    pub max_components: Option<u32>,
+    #[serde(default)]
+    pub constraints: Constraints,
+    /// Brings the circuit into the initial state. Without it, the circuit starts with all of
+    /// its inputs low.
+    pub reset: Option<Reset>,
+    /// The input pulsed to take a transition.
+    pub clock: usize,
+    /// Ticks the clock is held high and low for, after the inputs are set.
+    pub clock_ticks: u32,
+    pub initial_state: String,
+    pub states: Vec<FsmState>,
+    pub transitions: Vec<FsmTransition>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsmState {
+    pub name: String,
+    /// Outputs while in the state, ignoring those set to `null`.
+    pub outputs: Vec<Option<bool>>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsmTransition {
+    pub from: String,
+    /// The condition under which the transition is taken. Inputs set to `null` may take any
+    /// value, while the clock and reset inputs are ignored.
+    pub inputs: Vec<Option<bool>>,
+    pub to: String,
+}